@@ -0,0 +1,301 @@
+//! Attributes the size of a generated file to its original sources.
+//!
+//! Every byte of a minified or bundled file is attributed to the original
+//! source its token maps to.  Bytes that are not covered by a mapped token
+//! are reported as unmapped or whitespace and the `sourceMappingURL`
+//! comment is reported separately.  The sizes are aggregated per original
+//! file and as a directory tree, which makes it easy to find out what a
+//! bundle is made of.
+//!
+//! ```rust
+//! use sourcemap::analysis::analyze_bundle;
+//! use sourcemap::{SourceMapBuilder, SourceView};
+//!
+//! let generated = SourceView::new("a();b();\n//# sourceMappingURL=app.js.map".into());
+//! let mut builder = SourceMapBuilder::new(None);
+//! builder.add(0, 0, 0, 0, Some("src/a.js"), None, false);
+//! builder.add(0, 4, 0, 0, Some("node_modules/b/index.js"), None, false);
+//! let sm = builder.into_sourcemap();
+//!
+//! let report = analyze_bundle(&generated, &sm);
+//! assert_eq!(report.total_bytes, 40);
+//! assert_eq!(report.get_file("src/a.js").unwrap().bytes, 4);
+//! assert_eq!(report.source_mapping_url_bytes, 31);
+//! assert_eq!(report.tree.children[0].name, "node_modules");
+//!
+//! let mut json = vec![];
+//! report.to_writer(&mut json).unwrap();
+//! ```
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::errors::Result;
+use crate::sourceview::SourceView;
+use crate::types::{SourceMap, Token};
+
+/// The number of generated bytes attributed to an original file.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileSize {
+    /// the source as returned by [`SourceMap::get_source`]
+    pub path: String,
+    /// the number of generated bytes
+    pub bytes: u64,
+}
+
+/// A directory or file in the tree of original sources.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DirectorySize {
+    /// the name of the path segment, empty for the root
+    pub name: String,
+    /// the number of generated bytes of all files below this node
+    pub bytes: u64,
+    /// the entries of the directory sorted by name, empty for files
+    pub children: Vec<DirectorySize>,
+}
+
+impl DirectorySize {
+    fn new(name: &str) -> DirectorySize {
+        DirectorySize {
+            name: name.to_string(),
+            bytes: 0,
+            children: vec![],
+        }
+    }
+
+    fn insert(&mut self, path: &[&str], bytes: u64) {
+        self.bytes += bytes;
+        let (name, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        let idx = match self.children.iter().position(|c| c.name == *name) {
+            Some(idx) => idx,
+            None => {
+                self.children.push(DirectorySize::new(name));
+                self.children.len() - 1
+            }
+        };
+        self.children[idx].insert(rest, bytes);
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| a.name.cmp(&b.name));
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+
+    /// Looks up a node by its path segments.
+    pub fn get(&self, path: &[&str]) -> Option<&DirectorySize> {
+        match path.split_first() {
+            Some((name, rest)) => self.children.iter().find(|c| c.name == *name)?.get(rest),
+            None => Some(self),
+        }
+    }
+}
+
+/// The size attribution of a generated file.
+///
+/// All sizes are in bytes of the UTF-8 encoded generated file.  The sizes
+/// of the files plus the unmapped, whitespace and `sourceMappingURL`
+/// comment bytes add up to the total size.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleReport {
+    /// the size of the generated file
+    pub total_bytes: u64,
+    /// the bytes attributed to original files
+    pub mapped_bytes: u64,
+    /// the bytes not covered by a token with a source
+    pub unmapped_bytes: u64,
+    /// line terminators and unmapped whitespace
+    pub whitespace_bytes: u64,
+    /// the bytes of the `sourceMappingURL` comment
+    pub source_mapping_url_bytes: u64,
+    /// the original files sorted by size, largest first
+    pub files: Vec<FileSize>,
+    /// the original files as a directory tree.  Paths are split at `/` and
+    /// empty and `.` segments are skipped.
+    pub tree: DirectorySize,
+}
+
+impl BundleReport {
+    /// Returns the size of a single original file.
+    pub fn get_file(&self, path: &str) -> Option<&FileSize> {
+        self.files.iter().find(|file| file.path == path)
+    }
+
+    /// Writes the report as JSON.
+    pub fn to_writer<W: Write>(&self, w: W) -> Result<()> {
+        serde_json::to_writer(w, self)?;
+        Ok(())
+    }
+}
+
+/// Splits a source into lines and their terminators.
+fn split_lines(source: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = source;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest.find(['\r', '\n']).unwrap_or(rest.len());
+        let terminator_len = match rest.as_bytes().get(end..end + 2) {
+            Some(b"\r\n") => 2,
+            _ if end < rest.len() => 1,
+            _ => 0,
+        };
+        let (line, terminator) = (&rest[..end], &rest[end..end + terminator_len]);
+        rest = &rest[end + terminator_len..];
+        Some((line, terminator))
+    })
+}
+
+/// Returns the byte offset of a `sourceMappingURL` comment in a line.
+fn find_source_mapping_url(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let comment = trimmed
+        .strip_prefix("//# ")
+        .or_else(|| trimmed.strip_prefix("//@ "))
+        .or_else(|| trimmed.strip_prefix("/*# "))?;
+    comment
+        .starts_with("sourceMappingURL=")
+        .then(|| line.len() - trimmed.len())
+}
+
+/// A line of a generated file split into the parts covered by tokens.
+pub(crate) struct GeneratedLine<'a> {
+    /// the 0-indexed line number
+    pub idx: u32,
+    /// the line terminator
+    pub terminator: &'a str,
+    /// the `sourceMappingURL` comment, empty if the line has none
+    pub source_mapping_url: &'a str,
+    /// the code of the line as consecutive chunks with their start column
+    /// and the token that covers them
+    pub chunks: Vec<(u32, Option<Token<'a>>, &'a str)>,
+}
+
+/// Splits the lines of a generated file at the generated spans of the
+/// tokens of its sourcemap.
+///
+/// Code before the first token of a line is not covered by any token.
+pub(crate) fn generated_lines<'a>(
+    sm: &'a SourceMap,
+    generated: &'a SourceView,
+) -> impl Iterator<Item = GeneratedLine<'a>> {
+    let mut spans = sm.generated_spans(Some(generated)).peekable();
+    split_lines(generated.source())
+        .enumerate()
+        .map(move |(idx, (line, terminator))| {
+            let idx = idx as u32;
+            while spans.next_if(|span| span.start.0 < idx).is_some() {}
+
+            let code_end = find_source_mapping_url(line).unwrap_or(line.len());
+            let code = &line[..code_end];
+
+            // the byte offset and column of the end of the last chunk
+            let (mut offset, mut col) = (0, 0);
+            let mut chunks = vec![];
+            let mut push_chunk = |end_col: u32, token: Option<Token<'a>>| {
+                let (start, start_col) = (offset, col);
+                for c in code[offset..].chars() {
+                    if col >= end_col {
+                        break;
+                    }
+                    offset += c.len_utf8();
+                    col += c.len_utf16() as u32;
+                }
+                if offset > start {
+                    chunks.push((start_col, token, &code[start..offset]));
+                }
+            };
+
+            let mut token = None;
+            while let Some(span) = spans.next_if(|span| span.start.0 == idx) {
+                push_chunk(span.start.1, token);
+                token = Some(span.token);
+                push_chunk(span.end.map_or(u32::MAX, |end| end.1), token);
+            }
+            push_chunk(u32::MAX, token);
+
+            GeneratedLine {
+                idx,
+                terminator,
+                source_mapping_url: &line[code_end..],
+                chunks,
+            }
+        })
+}
+
+/// Attributes every byte of a generated file to an original source.
+///
+/// `generated` is the source of the generated file and `sm` its sourcemap.
+/// A token covers the generated file up to the next token on the same
+/// line or the end of the line.  Bytes before the first token of a line
+/// and bytes of tokens without a source are unmapped.
+pub fn analyze_bundle(generated: &SourceView, sm: &SourceMap) -> BundleReport {
+    let source = generated.source();
+    let mut sizes = vec![0u64; sm.get_source_count() as usize];
+    let mut report = BundleReport {
+        total_bytes: source.len() as u64,
+        mapped_bytes: 0,
+        unmapped_bytes: 0,
+        whitespace_bytes: 0,
+        source_mapping_url_bytes: 0,
+        files: vec![],
+        tree: DirectorySize::new(""),
+    };
+
+    for line in generated_lines(sm, generated) {
+        report.source_mapping_url_bytes += line.source_mapping_url.len() as u64;
+        report.whitespace_bytes += line.terminator.len() as u64;
+
+        for (_, token, chunk) in line.chunks {
+            let src_id = token.map_or(!0, |token| token.get_src_id());
+            match sizes.get_mut(src_id as usize) {
+                Some(size) => *size += chunk.len() as u64,
+                None => {
+                    for c in chunk.chars() {
+                        let bytes = c.len_utf8() as u64;
+                        if c.is_whitespace() {
+                            report.whitespace_bytes += bytes;
+                        } else {
+                            report.unmapped_bytes += bytes;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut files: BTreeMap<&str, u64> = BTreeMap::new();
+    for (src_id, &bytes) in sizes.iter().enumerate() {
+        if bytes > 0 {
+            let path = sm.get_source(src_id as u32).unwrap_or_default();
+            *files.entry(path).or_default() += bytes;
+        }
+    }
+    for (&path, &bytes) in &files {
+        let segments: Vec<&str> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect();
+        report.tree.insert(&segments, bytes);
+        report.mapped_bytes += bytes;
+    }
+    report.tree.sort();
+    report.files = files
+        .into_iter()
+        .map(|(path, bytes)| FileSize {
+            path: path.to_string(),
+            bytes,
+        })
+        .collect();
+    report
+        .files
+        .sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    report
+}
@@ -0,0 +1,445 @@
+//! A compact binary representation of a decoded [`SourceMap`].
+//!
+//! The format is meant for caching and is laid out so that it can be
+//! loaded without any VLQ decoding.  All integers are little endian `u32`s.
+//! The sections follow each other without padding, so all of them except
+//! the string data hold whole `u32`s and start at a multiple of 4 bytes
+//! from the start of the buffer.  The string data is not padded:
+//!
+//! | section        | contents                                                      |
+//! |----------------|---------------------------------------------------------------|
+//! | header         | magic `SMCB`, version, token, source, name, ignore and string counts, string indexes of file, source root and debug id |
+//! | tokens         | `dst_line, dst_col, src_line, src_col, src_id, name_id, flags` sorted by destination |
+//! | sources        | string indexes of the source name and contents per source     |
+//! | names          | string index per name                                         |
+//! | ignore list    | source index per entry                                        |
+//! | string offsets | `string_count + 1` byte offsets into the string data          |
+//! | string data    | UTF-8 bytes of all strings                                    |
+//!
+//! Missing values are stored as `u32::MAX`.  Scope information
+//! (`originalScopes` and `generatedRanges`) and extension fields are not
+//! stored in the format and are lost when a sourcemap is written as a
+//! binary cache.
+//!
+//! [`BinarySourceMap`] reads the format in place while
+//! [`SourceMap::from_binary_slice`] copies it into a [`SourceMap`].
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io::Write;
+use std::sync::Arc;
+
+use debugid::DebugId;
+
+use crate::errors::{Error, Result};
+use crate::types::{RawToken, SourceMap};
+
+const MAGIC: &[u8; 4] = b"SMCB";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 44;
+const TOKEN_SIZE: usize = 28;
+
+const TOKEN_FLAG_RANGE: u32 = 0x1;
+
+/// Collects strings for the string table.
+#[derive(Default)]
+struct StringTable<'a> {
+    strings: Vec<&'a str>,
+}
+
+impl<'a> StringTable<'a> {
+    fn add(&mut self, s: Option<&'a str>) -> u32 {
+        match s {
+            Some(s) => {
+                self.strings.push(s);
+                (self.strings.len() - 1) as u32
+            }
+            None => !0,
+        }
+    }
+}
+
+fn put(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn to_u32(value: usize) -> Result<u32> {
+    u32::try_from(value).map_err(|_| Error::InvalidBinaryCache)
+}
+
+pub fn encode_binary<W: Write>(sm: &SourceMap, mut w: W) -> Result<()> {
+    let mut strings = StringTable::default();
+    let debug_id = sm.get_debug_id().map(|id| id.to_string());
+    let file = strings.add(sm.get_file());
+    let source_root = strings.add(sm.get_source_root());
+    let debug_id = strings.add(debug_id.as_deref());
+    let sources: Vec<_> = sm
+        .sources
+        .iter()
+        .enumerate()
+        .map(|(idx, source)| {
+            (
+                strings.add(Some(source)),
+                strings.add(sm.get_source_contents(idx as u32)),
+            )
+        })
+        .collect();
+    let names: Vec<_> = sm
+        .names
+        .iter()
+        .map(|name| strings.add(Some(name)))
+        .collect();
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + sm.tokens.len() * TOKEN_SIZE);
+    out.extend_from_slice(MAGIC);
+    for value in [
+        VERSION,
+        to_u32(sm.tokens.len())?,
+        to_u32(sources.len())?,
+        to_u32(names.len())?,
+        to_u32(sm.ignore_list.len())?,
+        to_u32(strings.strings.len())?,
+        file,
+        source_root,
+        debug_id,
+    ] {
+        put(&mut out, value);
+    }
+    // reserved for future use
+    put(&mut out, 0);
+
+    for token in &sm.tokens {
+        put(&mut out, token.dst_line);
+        put(&mut out, token.dst_col);
+        put(&mut out, token.src_line);
+        put(&mut out, token.src_col);
+        put(&mut out, token.src_id);
+        put(&mut out, token.name_id);
+        put(&mut out, if token.is_range { TOKEN_FLAG_RANGE } else { 0 });
+    }
+    for (source, contents) in sources {
+        put(&mut out, source);
+        put(&mut out, contents);
+    }
+    for name in names {
+        put(&mut out, name);
+    }
+    for &idx in &sm.ignore_list {
+        put(&mut out, idx);
+    }
+
+    let mut offset = 0;
+    put(&mut out, 0);
+    for s in &strings.strings {
+        offset = to_u32(offset as usize + s.len())?;
+        put(&mut out, offset);
+    }
+    w.write_all(&out)?;
+    for s in &strings.strings {
+        w.write_all(s.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Reads little endian `u32`s from a byte slice.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(Error::InvalidBinaryCache);
+        }
+        let (rv, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(rv)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// Reads the `idx`th little endian `u32` of a section.
+///
+/// Sections are sized when the view is created so this cannot go out of
+/// bounds for valid indexes.
+fn read_u32(data: &[u8], idx: usize) -> u32 {
+    let bytes = &data[idx * 4..idx * 4 + 4];
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// A view over a binary sourcemap cache that decodes nothing upfront.
+///
+/// Creating the view only checks the header and the section sizes, which
+/// makes it suitable for memory mapped files.  Lookups binary search the
+/// token table in place and strings are sliced from the string table when
+/// they are requested.  Malformed strings and references are only detected
+/// at that point; accessors return `None` for them.
+///
+/// ```rust
+/// use sourcemap::{BinarySourceMap, SourceMapBuilder};
+/// let mut builder = SourceMapBuilder::new(Some("bundle.js"));
+/// builder.add(0, 0, 1, 0, Some("a.js"), Some("foo"), false);
+/// builder.add(0, 8, 4, 2, Some("b.js"), None, false);
+/// let mut cache = vec![];
+/// builder.into_sourcemap().to_binary_writer(&mut cache).unwrap();
+///
+/// let sm = BinarySourceMap::from_slice(&cache).unwrap();
+/// let token = sm.lookup_token(0, 10).unwrap();
+/// assert_eq!(sm.get_source(token.src_id).as_deref(), Some("b.js"));
+/// assert_eq!((token.src_line, token.src_col), (4, 2));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BinarySourceMap<'a> {
+    token_data: &'a [u8],
+    source_data: &'a [u8],
+    name_data: &'a [u8],
+    ignore_data: &'a [u8],
+    offset_data: &'a [u8],
+    string_data: &'a [u8],
+    file: u32,
+    source_root: u32,
+    debug_id: u32,
+}
+
+impl<'a> BinarySourceMap<'a> {
+    /// Creates a view over a slice written by
+    /// [`SourceMap::to_binary_writer`].
+    pub fn from_slice(slice: &'a [u8]) -> Result<BinarySourceMap<'a>> {
+        let mut reader = Reader { data: slice };
+        if reader.take(4).ok() != Some(&MAGIC[..]) {
+            return Err(Error::InvalidBinaryCacheMagic);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedBinaryCacheVersion(version));
+        }
+        let token_count = reader.u32()? as usize;
+        let source_count = reader.u32()? as usize;
+        let name_count = reader.u32()? as usize;
+        let ignore_count = reader.u32()? as usize;
+        let string_count = reader.u32()? as usize;
+        let file = reader.u32()?;
+        let source_root = reader.u32()?;
+        let debug_id = reader.u32()?;
+        reader.u32()?;
+
+        Ok(BinarySourceMap {
+            token_data: reader.take(token_count.saturating_mul(TOKEN_SIZE))?,
+            source_data: reader.take(source_count.saturating_mul(8))?,
+            name_data: reader.take(name_count.saturating_mul(4))?,
+            ignore_data: reader.take(ignore_count.saturating_mul(4))?,
+            offset_data: reader.take(string_count.saturating_add(1).saturating_mul(4))?,
+            string_data: reader.data,
+            file,
+            source_root,
+            debug_id,
+        })
+    }
+
+    fn get_string(&self, idx: u32) -> Result<Option<&'a str>> {
+        if idx == !0 {
+            return Ok(None);
+        }
+        let idx = idx as usize;
+        if idx + 1 >= self.offset_data.len() / 4 {
+            return Err(Error::InvalidBinaryCache);
+        }
+        let start = read_u32(self.offset_data, idx) as usize;
+        let end = read_u32(self.offset_data, idx + 1) as usize;
+        let bytes = self
+            .string_data
+            .get(start..end)
+            .ok_or(Error::InvalidBinaryCache)?;
+        Ok(Some(std::str::from_utf8(bytes)?))
+    }
+
+    /// Returns the embedded filename in case there is one.
+    pub fn get_file(&self) -> Option<&'a str> {
+        self.get_string(self.file).ok()?
+    }
+
+    /// Returns the embedded source_root in case there is one.
+    pub fn get_source_root(&self) -> Option<&'a str> {
+        self.get_string(self.source_root).ok()?
+    }
+
+    /// Returns the embedded debug id.
+    pub fn get_debug_id(&self) -> Option<DebugId> {
+        self.get_string(self.debug_id).ok()??.parse().ok()
+    }
+
+    /// Returns the number of tokens in the sourcemap.
+    pub fn get_token_count(&self) -> u32 {
+        (self.token_data.len() / TOKEN_SIZE) as u32
+    }
+
+    /// Reads a token by its index.
+    pub fn get_token(&self, idx: u32) -> Option<RawToken> {
+        if idx >= self.get_token_count() {
+            return None;
+        }
+        let data = &self.token_data[idx as usize * TOKEN_SIZE..];
+        Some(RawToken {
+            dst_line: read_u32(data, 0),
+            dst_col: read_u32(data, 1),
+            src_line: read_u32(data, 2),
+            src_col: read_u32(data, 3),
+            src_id: read_u32(data, 4),
+            name_id: read_u32(data, 5),
+            is_range: read_u32(data, 6) & TOKEN_FLAG_RANGE != 0,
+        })
+    }
+
+    fn get_dst(&self, idx: u32) -> (u32, u32) {
+        let data = &self.token_data[idx as usize * TOKEN_SIZE..];
+        (read_u32(data, 0), read_u32(data, 1))
+    }
+
+    /// Looks up the closest token to a given 0-indexed line and column.
+    ///
+    /// This behaves like [`SourceMap::lookup_token`] but returns the raw
+    /// token.  Range mappings are applied to the source column of the
+    /// returned token.
+    pub fn lookup_token(&self, line: u32, col: u32) -> Option<RawToken> {
+        let (mut low, mut high) = (0, self.get_token_count());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.get_dst(mid) <= (line, col) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let mut idx = low.checked_sub(1)?;
+        // like `SourceMap::lookup_token` prefer the first of several tokens
+        // at the same position
+        while idx > 0 && self.get_dst(idx - 1) == self.get_dst(idx) {
+            idx -= 1;
+        }
+
+        let mut token = self.get_token(idx)?;
+        if token.is_range && token.dst_line == line && token.src_col != !0 {
+            token.src_col += col - token.dst_col;
+        }
+        Some(token)
+    }
+
+    /// Returns the number of sources in the sourcemap.
+    pub fn get_source_count(&self) -> u32 {
+        (self.source_data.len() / 8) as u32
+    }
+
+    /// Looks up a source for a specific index.
+    ///
+    /// The `sourceRoot` is already applied to the returned value.
+    pub fn get_source(&self, idx: u32) -> Option<Cow<'a, str>> {
+        let source = self.get_raw_source(idx)?;
+        match self.get_source_root().filter(|root| !root.is_empty()) {
+            Some(source_root) => Some(Cow::Owned(
+                SourceMap::prefix_source(source_root, source).to_string(),
+            )),
+            None => Some(Cow::Borrowed(source)),
+        }
+    }
+
+    fn get_raw_source(&self, idx: u32) -> Option<&'a str> {
+        if idx >= self.get_source_count() {
+            return None;
+        }
+        self.get_string(read_u32(self.source_data, idx as usize * 2))
+            .ok()?
+    }
+
+    /// Looks up the content for a source.
+    pub fn get_source_contents(&self, idx: u32) -> Option<&'a str> {
+        if idx >= self.get_source_count() {
+            return None;
+        }
+        self.get_string(read_u32(self.source_data, idx as usize * 2 + 1))
+            .ok()?
+    }
+
+    /// Returns the number of names in the sourcemap.
+    pub fn get_name_count(&self) -> u32 {
+        (self.name_data.len() / 4) as u32
+    }
+
+    /// Looks up a name for a specific index.
+    pub fn get_name(&self, idx: u32) -> Option<&'a str> {
+        if idx >= self.get_name_count() {
+            return None;
+        }
+        self.get_string(read_u32(self.name_data, idx as usize))
+            .ok()?
+    }
+
+    /// Returns the source indexes that devtools should ignore.
+    pub fn ignore_list(&self) -> impl Iterator<Item = u32> + 'a {
+        let data = self.ignore_data;
+        (0..data.len() / 4).map(move |idx| read_u32(data, idx))
+    }
+
+    /// Copies everything into an owned [`SourceMap`].
+    ///
+    /// Unlike the accessors of the view this validates all tokens and
+    /// strings.
+    pub fn to_sourcemap(&self) -> Result<SourceMap> {
+        let source_count = self.get_source_count();
+        let name_count = self.get_name_count();
+        let tokens = (0..self.get_token_count())
+            .filter_map(|idx| self.get_token(idx))
+            .map(|token| {
+                if token.src_id != !0 && token.src_id >= source_count {
+                    return Err(Error::BadSourceReference(token.src_id));
+                }
+                if token.name_id != !0 && token.name_id >= name_count {
+                    return Err(Error::BadNameReference(token.name_id));
+                }
+                Ok(token)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut sources = Vec::with_capacity(source_count as usize);
+        let mut sources_content = Vec::with_capacity(source_count as usize);
+        for idx in 0..source_count as usize {
+            let source = self.get_string(read_u32(self.source_data, idx * 2))?;
+            sources.push(Arc::from(source.unwrap_or_default()));
+            let contents = self.get_string(read_u32(self.source_data, idx * 2 + 1))?;
+            sources_content.push(contents.map(Arc::from));
+        }
+
+        let names = (0..name_count as usize)
+            .map(|idx| {
+                let name = self.get_string(read_u32(self.name_data, idx))?;
+                Ok(Arc::from(name.unwrap_or_default()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut sm = SourceMap::new(
+            self.get_string(self.file)?.map(Into::into),
+            tokens,
+            names,
+            sources,
+            Some(sources_content).filter(|contents| contents.iter().any(Option::is_some)),
+        );
+        sm.set_source_root(self.get_string(self.source_root)?);
+        if let Some(debug_id) = self.get_string(self.debug_id)? {
+            sm.set_debug_id(Some(
+                debug_id.parse().map_err(|_| Error::InvalidBinaryCache)?,
+            ));
+        }
+        for idx in self.ignore_list() {
+            sm.add_to_ignore_list(idx);
+        }
+
+        Ok(sm)
+    }
+}
+
+pub fn decode_binary(slice: &[u8]) -> Result<SourceMap> {
+    BinarySourceMap::from_slice(slice)?.to_sourcemap()
+}
@@ -0,0 +1,242 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use debugid::DebugId;
+use serde_json::value::RawValue;
+
+use crate::decoder::{decode_borrowed_parts, MappingsIter};
+use crate::errors::Result;
+use crate::jsontypes::parse_json_str;
+use crate::sourceview::SourceView;
+use crate::types::{
+    get_token, lookup_token, LookupOptions, RawToken, SourceMap, Token, TokenStore,
+};
+
+/// A sourcemap that borrows its strings from the input buffer.
+///
+/// `sources` and `names` are borrowed from the buffer unless they contain
+/// escape sequences.  `sourcesContent` is kept as raw JSON until a source
+/// view is requested for it, at which point only that source is decoded.
+/// This makes decoding considerably cheaper than [`SourceMap::from_slice`]
+/// when only a few lookups are performed.
+///
+/// The `mappings` are decoded upfront so that lookups are as fast as on a
+/// [`SourceMap`].  Vendor extensions and scope information are skipped.
+///
+/// ```rust
+/// use sourcemap::BorrowedSourceMap;
+/// let input: &[_] = b"{
+///     \"version\":3,
+///     \"sources\":[\"coolstuff.js\"],
+///     \"names\":[\"x\",\"alert\"],
+///     \"mappings\":\"AAAA,GAAIA,GAAI,EACR,IAAIA,GAAK,EAAG,CACVC,MAAM\"
+/// }";
+/// let sm = BorrowedSourceMap::from_slice(input).unwrap();
+/// let token = sm.lookup_token(0, 3).unwrap();
+/// assert_eq!(token.to_tuple(), ("coolstuff.js", 0, 4, Some("x")));
+/// ```
+pub struct BorrowedSourceMap<'a> {
+    file: Option<Cow<'a, str>>,
+    source_root: Option<Arc<str>>,
+    sources: Vec<Cow<'a, str>>,
+    sources_prefixed: Option<Vec<Arc<str>>>,
+    sources_content: Vec<&'a RawValue>,
+    source_views: Vec<OnceLock<Option<SourceView>>>,
+    names: Vec<Cow<'a, str>>,
+    tokens: Vec<RawToken>,
+    ignore_list: BTreeSet<u32>,
+    debug_id: Option<DebugId>,
+}
+
+impl<'a> BorrowedSourceMap<'a> {
+    /// Decodes a sourcemap from a JSON byte slice in UTF-8 format without
+    /// copying its strings.
+    ///
+    /// Optionally a "garbage header" as defined by the sourcemap draft
+    /// specification is supported.  In case an indexed sourcemap is
+    /// encountered an error is returned.
+    pub fn from_slice(slice: &'a [u8]) -> Result<BorrowedSourceMap<'a>> {
+        let parts = decode_borrowed_parts(slice)?;
+        let mut tokens = MappingsIter::new(
+            &parts.mappings,
+            parts.range_mappings.as_deref(),
+            parts.sources.len() as u32,
+            parts.names.len() as u32,
+        )
+        .collect::<Result<Vec<_>>>()?;
+        tokens.sort_unstable_by_key(|t| (t.dst_line, t.dst_col));
+
+        let source_views = parts
+            .sources_content
+            .iter()
+            .map(|_| OnceLock::new())
+            .collect();
+
+        Ok(BorrowedSourceMap {
+            file: parts.file.map(Into::into),
+            source_root: parts.source_root,
+            sources: parts.sources,
+            sources_prefixed: parts.sources_prefixed,
+            sources_content: parts.sources_content,
+            source_views,
+            names: parts.names,
+            tokens,
+            ignore_list: parts.ignore_list.into_iter().collect(),
+            debug_id: parts.debug_id,
+        })
+    }
+
+    /// Returns the embedded filename in case there is one.
+    pub fn get_file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns the embedded source_root in case there is one.
+    pub fn get_source_root(&self) -> Option<&str> {
+        self.source_root.as_deref()
+    }
+
+    /// Returns the embedded debug id.
+    pub fn get_debug_id(&self) -> Option<DebugId> {
+        self.debug_id
+    }
+
+    /// Returns the source indexes that devtools should ignore.
+    pub fn ignore_list(&self) -> impl Iterator<Item = &u32> {
+        self.ignore_list.iter()
+    }
+
+    /// Looks up a token by its index.
+    pub fn get_token(&self, idx: usize) -> Option<BorrowedToken<'_>> {
+        get_token(self, idx)
+    }
+
+    /// Returns the number of tokens in the sourcemap.
+    pub fn get_token_count(&self) -> u32 {
+        self.tokens.len() as u32
+    }
+
+    /// Returns an iterator over the tokens.
+    pub fn tokens(&self) -> impl Iterator<Item = BorrowedToken<'_>> {
+        (0..self.tokens.len()).filter_map(move |idx| self.get_token(idx))
+    }
+
+    /// Looks up the closest token to a given 0-indexed line and column.
+    ///
+    /// See [`SourceMap::lookup_token`].
+    pub fn lookup_token(&self, line: u32, col: u32) -> Option<BorrowedToken<'_>> {
+        lookup_token(self, line, col, &LookupOptions::default())
+    }
+
+    /// Looks up the closest token to a given 0-indexed line and column with
+    /// the given options.
+    ///
+    /// See [`SourceMap::lookup_token_with_options`].
+    pub fn lookup_token_with_options(
+        &self,
+        line: u32,
+        col: u32,
+        options: &LookupOptions,
+    ) -> Option<BorrowedToken<'_>> {
+        lookup_token(self, line, col, options)
+    }
+
+    /// Returns the number of sources in the sourcemap.
+    pub fn get_source_count(&self) -> u32 {
+        self.sources.len() as u32
+    }
+
+    /// Looks up a source for a specific index.
+    pub fn get_source(&self, idx: u32) -> Option<&str> {
+        match self.sources_prefixed {
+            Some(ref sources) => sources.get(idx as usize).map(|x| &x[..]),
+            None => self.sources.get(idx as usize).map(|x| &x[..]),
+        }
+    }
+
+    /// Returns the sources content as source view.
+    ///
+    /// The contents of a source are decoded the first time they are
+    /// requested.
+    pub fn get_source_view(&self, idx: u32) -> Option<&SourceView> {
+        let raw = self.sources_content.get(idx as usize)?;
+        self.source_views[idx as usize]
+            .get_or_init(|| {
+                let contents = parse_json_str(raw).ok()??;
+                Some(SourceView::new(contents.into()))
+            })
+            .as_ref()
+    }
+
+    /// Looks up the content for a source.
+    pub fn get_source_contents(&self, idx: u32) -> Option<&str> {
+        self.get_source_view(idx).map(SourceView::source)
+    }
+
+    /// Returns the number of names in the sourcemap.
+    pub fn get_name_count(&self) -> u32 {
+        self.names.len() as u32
+    }
+
+    /// Looks up a name for a specific index.
+    pub fn get_name(&self, idx: u32) -> Option<&str> {
+        self.names.get(idx as usize).map(|x| &x[..])
+    }
+
+    /// Copies everything into an owned [`SourceMap`].
+    pub fn to_sourcemap(&self) -> SourceMap {
+        let sources_content = (0..self.sources_content.len() as u32)
+            .map(|idx| self.get_source_contents(idx).map(Into::into))
+            .collect();
+        let mut sm = SourceMap::new(
+            self.file.as_deref().map(Into::into),
+            self.tokens.clone(),
+            self.names.iter().map(|name| name[..].into()).collect(),
+            self.sources
+                .iter()
+                .map(|source| source[..].into())
+                .collect(),
+            Some(sources_content),
+        );
+        sm.set_source_root(self.source_root.clone());
+        sm.set_debug_id(self.debug_id);
+        for &idx in &self.ignore_list {
+            sm.add_to_ignore_list(idx);
+        }
+        sm
+    }
+}
+
+impl fmt::Debug for BorrowedSourceMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BorrowedSourceMap")
+            .field("file", &self.file)
+            .field("sources", &self.sources)
+            .field("names", &self.names)
+            .field("tokens", &self.tokens.len())
+            .finish()
+    }
+}
+
+/// Represents a token from a [`BorrowedSourceMap`].
+pub type BorrowedToken<'a> = Token<'a, BorrowedSourceMap<'a>>;
+
+impl TokenStore for BorrowedSourceMap<'_> {
+    fn raw_tokens(&self) -> &[RawToken] {
+        &self.tokens
+    }
+
+    fn get_source(&self, idx: u32) -> Option<&str> {
+        BorrowedSourceMap::get_source(self, idx)
+    }
+
+    fn get_name(&self, idx: u32) -> Option<&str> {
+        BorrowedSourceMap::get_name(self, idx)
+    }
+
+    fn get_source_view(&self, idx: u32) -> Option<&SourceView> {
+        BorrowedSourceMap::get_source_view(self, idx)
+    }
+}
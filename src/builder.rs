@@ -12,7 +12,8 @@ use rustc_hash::FxHashMap;
 use url::Url;
 
 use crate::errors::Result;
-use crate::types::{RawToken, SourceMap, Token};
+use crate::scopes::{GeneratedRange, OriginalScope};
+use crate::types::{DecodedMap, RawToken, SourceMap, SourceMapIndex, SourceMapSection, Token};
 
 /// Helper for sourcemap generation
 ///
@@ -29,6 +30,8 @@ pub struct SourceMapBuilder {
     sources: Vec<Arc<str>>,
     source_contents: Vec<Option<Arc<str>>>,
     sources_mapping: Vec<u32>,
+    original_scopes: Vec<Option<OriginalScope>>,
+    generated_ranges: Vec<GeneratedRange>,
     ignore_list: BTreeSet<u32>,
     debug_id: Option<DebugId>,
 }
@@ -63,6 +66,8 @@ impl SourceMapBuilder {
             sources: vec![],
             source_contents: vec![],
             sources_mapping: vec![],
+            original_scopes: vec![],
+            generated_ranges: vec![],
             ignore_list: BTreeSet::default(),
             debug_id: None,
         }
@@ -144,6 +149,30 @@ impl SourceMapBuilder {
         self.get_source_contents(src_id).is_some()
     }
 
+    /// Sets the original scope tree for an already existing source.
+    pub fn set_original_scope(&mut self, src_id: u32, scope: Option<OriginalScope>) {
+        assert!(src_id != !0, "Cannot set scopes for tombstone source id");
+        if self.sources.len() > self.original_scopes.len() {
+            self.original_scopes.resize(self.sources.len(), None);
+        }
+        self.original_scopes[src_id as usize] = scope;
+    }
+
+    /// Returns the original scope tree for a source.
+    pub fn get_original_scope(&self, src_id: u32) -> Option<&OriginalScope> {
+        self.original_scopes
+            .get(src_id as usize)
+            .and_then(Option::as_ref)
+    }
+
+    /// Adds a top-level range in the generated file.
+    ///
+    /// Ranges can be added in any order and are sorted by their start
+    /// position when the sourcemap is built.  They must not overlap.
+    pub fn add_generated_range(&mut self, range: GeneratedRange) {
+        self.generated_ranges.push(range);
+    }
+
     /// Loads source contents from locally accessible files if referenced
     /// accordingly.  Returns the number of loaded source contents
     #[cfg(any(unix, windows, target_os = "redox"))]
@@ -314,7 +343,122 @@ impl SourceMapBuilder {
         for ignored_src_id in self.ignore_list {
             sm.add_to_ignore_list(ignored_src_id);
         }
+        for (src_id, scope) in self.original_scopes.into_iter().enumerate() {
+            if scope.is_some() {
+                sm.set_original_scope(src_id as u32, scope);
+            }
+        }
+        sm.set_generated_ranges(self.generated_ranges);
 
         sm
     }
 }
+
+/// Helper for sourcemap index generation
+///
+/// This helper builds a sourcemap index for a file that is created by
+/// concatenating chunks of generated code.  The offsets of the sections are
+/// computed from the line and column counts of the chunks, so chunks do not
+/// need to end in a newline.
+pub struct SourceMapIndexBuilder {
+    file: Option<String>,
+    sections: Vec<SourceMapSection>,
+    offset: (u32, u32),
+    debug_id: Option<DebugId>,
+}
+
+impl SourceMapIndexBuilder {
+    /// Creates a new sourcemap index builder and sets the file.
+    pub fn new(file: Option<&str>) -> SourceMapIndexBuilder {
+        SourceMapIndexBuilder {
+            file: file.map(str::to_owned),
+            sections: vec![],
+            offset: (0, 0),
+            debug_id: None,
+        }
+    }
+
+    /// Sets the debug id for the sourcemap index (optional)
+    pub fn set_debug_id(&mut self, debug_id: Option<DebugId>) {
+        self.debug_id = debug_id;
+    }
+
+    /// Sets the file for the sourcemap index (optional)
+    pub fn set_file(&mut self, value: Option<&str>) {
+        self.file = value.map(str::to_owned);
+    }
+
+    /// Returns the currently set file.
+    pub fn get_file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns the line and column where the next chunk will start.
+    pub fn get_offset(&self) -> (u32, u32) {
+        self.offset
+    }
+
+    /// Appends a chunk of generated code and returns its offset.
+    ///
+    /// If a sourcemap is given a section for it is added at the offset of
+    /// the chunk.  Columns are counted in UTF-16 code units, like in
+    /// JavaScript.
+    pub fn add_chunk(&mut self, code: &str, map: Option<DecodedMap>) -> (u32, u32) {
+        self.add_chunk_impl(code, None, map)
+    }
+
+    /// Appends a chunk of generated code whose sourcemap is referenced by a
+    /// URL and returns its offset.
+    pub fn add_chunk_with_url(&mut self, code: &str, url: &str) -> (u32, u32) {
+        self.add_chunk_impl(code, Some(url.to_owned()), None)
+    }
+
+    fn add_chunk_impl(
+        &mut self,
+        code: &str,
+        url: Option<String>,
+        map: Option<DecodedMap>,
+    ) -> (u32, u32) {
+        let offset = self.offset;
+        if url.is_some() || map.is_some() {
+            self.sections.push(SourceMapSection::new(offset, url, map));
+        }
+        self.advance(code);
+        offset
+    }
+
+    fn advance(&mut self, code: &str) {
+        let (mut line, mut col) = self.offset;
+        let mut chars = code.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' => {
+                    chars.next_if_eq(&'\n');
+                    line += 1;
+                    col = 0;
+                }
+                '\n' => {
+                    line += 1;
+                    col = 0;
+                }
+                c => col += c.len_utf16() as u32,
+            }
+        }
+        self.offset = (line, col);
+    }
+
+    /// Converts the builder into a sourcemap index.
+    pub fn into_index(self) -> SourceMapIndex {
+        SourceMapIndex::new(self.file, self.sections).with_debug_id(self.debug_id)
+    }
+
+    /// Converts the builder into a flattened sourcemap.
+    ///
+    /// This fails if a chunk references its sourcemap by URL.
+    pub fn into_sourcemap(self) -> Result<SourceMap> {
+        let debug_id = self.debug_id;
+        let mut sm = self.into_index().flatten()?;
+        sm.set_debug_id(debug_id);
+        Ok(sm)
+    }
+}
@@ -0,0 +1,407 @@
+//! Remaps V8 code coverage through sourcemaps.
+//!
+//! V8 reports coverage for the generated (bundled or minified) file as
+//! ranges of offsets with execution counts.  This module maps those ranges
+//! back to the original sources and produces line, function and branch
+//! coverage per original file, which can be written as Istanbul JSON or
+//! lcov.
+//!
+//! ```rust
+//! use sourcemap::coverage::{remap_coverage, ScriptCoverage};
+//! use sourcemap::{SourceMapBuilder, SourceView};
+//!
+//! let generated = SourceView::new("function a(){}a();".into());
+//! let mut builder = SourceMapBuilder::new(None);
+//! builder.add(0, 0, 0, 0, Some("a.ts"), None, false);
+//! builder.add(0, 14, 2, 0, Some("a.ts"), None, false);
+//! let sm = builder.into_sourcemap();
+//!
+//! let coverage = ScriptCoverage::from_slice(br#"{
+//!     "scriptId": "1",
+//!     "url": "bundle.js",
+//!     "functions": [
+//!         {"functionName": "", "isBlockCoverage": false,
+//!          "ranges": [{"startOffset": 0, "endOffset": 18, "count": 1}]},
+//!         {"functionName": "a", "isBlockCoverage": false,
+//!          "ranges": [{"startOffset": 0, "endOffset": 14, "count": 1}]}
+//!     ]
+//! }"#).unwrap();
+//!
+//! let report = remap_coverage(&coverage, &generated, &sm);
+//! let file = report.get_file("a.ts").unwrap();
+//! // lines are 1-indexed
+//! assert_eq!(file.lines.get(&3), Some(&1));
+//! assert_eq!(file.functions[0].name, "a");
+//!
+//! let mut lcov = vec![];
+//! report.to_lcov_writer(&mut lcov).unwrap();
+//! assert!(String::from_utf8(lcov).unwrap().starts_with("TN:\nSF:a.ts\n"));
+//! ```
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::errors::Result;
+use crate::sourceview::SourceView;
+use crate::types::SourceMap;
+
+/// A range of the generated file with its execution count.
+///
+/// Offsets are counted in UTF-16 code units like JavaScript string
+/// indexes, which is what V8 reports.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageRange {
+    /// the start offset (inclusive)
+    pub start_offset: u32,
+    /// the end offset (exclusive)
+    pub end_offset: u32,
+    /// how often the range was executed
+    pub count: u32,
+}
+
+/// The coverage of a single function as reported by V8.
+///
+/// The first range covers the entire function, further ranges cover the
+/// blocks within it if block coverage was enabled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionCoverage {
+    /// the name of the function, empty for anonymous functions
+    #[serde(default)]
+    pub function_name: String,
+    /// the covered ranges
+    pub ranges: Vec<CoverageRange>,
+    /// true if the ranges include block coverage
+    #[serde(default)]
+    pub is_block_coverage: bool,
+}
+
+/// The coverage of a script as returned by V8's
+/// `Profiler.takePreciseCoverage` or written to `NODE_V8_COVERAGE`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptCoverage {
+    /// the V8 script id
+    #[serde(default)]
+    pub script_id: String,
+    /// the URL of the script
+    #[serde(default)]
+    pub url: String,
+    /// the coverage of the functions in the script
+    pub functions: Vec<FunctionCoverage>,
+}
+
+impl ScriptCoverage {
+    /// Parses script coverage from a JSON byte slice.
+    pub fn from_slice(slice: &[u8]) -> Result<ScriptCoverage> {
+        Ok(serde_json::from_slice(slice)?)
+    }
+}
+
+/// Coverage of a function in an original file.
+///
+/// Lines and columns are 0-indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionEntry {
+    /// the function name as reported by V8
+    pub name: String,
+    /// the original start position
+    pub start: (u32, u32),
+    /// the original end position
+    pub end: (u32, u32),
+    /// how often the function was called
+    pub count: u32,
+}
+
+/// Coverage of a block in an original file.
+///
+/// Lines and columns are 0-indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchEntry {
+    /// the original start position
+    pub start: (u32, u32),
+    /// the original end position
+    pub end: (u32, u32),
+    /// how often the block was executed
+    pub count: u32,
+}
+
+/// The remapped coverage of an original file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// the source as returned by [`SourceMap::get_source`]
+    pub path: String,
+    /// execution counts per 1-indexed line
+    pub lines: BTreeMap<u32, u32>,
+    /// the functions in the file
+    pub functions: Vec<FunctionEntry>,
+    /// the blocks in the file
+    pub branches: Vec<BranchEntry>,
+}
+
+impl FileCoverage {
+    fn new(path: &str) -> FileCoverage {
+        FileCoverage {
+            path: path.to_string(),
+            lines: BTreeMap::new(),
+            functions: vec![],
+            branches: vec![],
+        }
+    }
+
+    fn to_istanbul(&self) -> Value {
+        fn loc(start: (u32, u32), end: (u32, u32)) -> Value {
+            json!({
+                "start": {"line": start.0 + 1, "column": start.1},
+                "end": {"line": end.0 + 1, "column": end.1},
+            })
+        }
+
+        let mut statement_map = Map::new();
+        let mut s = Map::new();
+        for (idx, (&line, &count)) in self.lines.iter().enumerate() {
+            statement_map.insert(
+                idx.to_string(),
+                json!({
+                    "start": {"line": line, "column": 0},
+                    "end": {"line": line + 1, "column": 0},
+                }),
+            );
+            s.insert(idx.to_string(), count.into());
+        }
+
+        let mut fn_map = Map::new();
+        let mut f = Map::new();
+        for (idx, func) in self.functions.iter().enumerate() {
+            let loc = loc(func.start, func.end);
+            fn_map.insert(
+                idx.to_string(),
+                json!({
+                    "name": func.name,
+                    "decl": loc,
+                    "loc": loc,
+                    "line": func.start.0 + 1,
+                }),
+            );
+            f.insert(idx.to_string(), func.count.into());
+        }
+
+        let mut branch_map = Map::new();
+        let mut b = Map::new();
+        for (idx, branch) in self.branches.iter().enumerate() {
+            let loc = loc(branch.start, branch.end);
+            branch_map.insert(
+                idx.to_string(),
+                json!({
+                    "loc": loc,
+                    "type": "branch",
+                    "locations": [loc],
+                    "line": branch.start.0 + 1,
+                }),
+            );
+            b.insert(idx.to_string(), json!([branch.count]));
+        }
+
+        json!({
+            "path": self.path,
+            "statementMap": statement_map,
+            "fnMap": fn_map,
+            "branchMap": branch_map,
+            "s": s,
+            "f": f,
+            "b": b,
+        })
+    }
+
+    fn write_lcov<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "TN:")?;
+        writeln!(w, "SF:{}", self.path)?;
+        for func in &self.functions {
+            writeln!(w, "FN:{},{}", func.start.0 + 1, func.name)?;
+        }
+        for func in &self.functions {
+            writeln!(w, "FNDA:{},{}", func.count, func.name)?;
+        }
+        writeln!(w, "FNF:{}", self.functions.len())?;
+        writeln!(
+            w,
+            "FNH:{}",
+            self.functions.iter().filter(|f| f.count > 0).count()
+        )?;
+        for (idx, branch) in self.branches.iter().enumerate() {
+            writeln!(w, "BRDA:{},{},0,{}", branch.start.0 + 1, idx, branch.count)?;
+        }
+        writeln!(w, "BRF:{}", self.branches.len())?;
+        writeln!(
+            w,
+            "BRH:{}",
+            self.branches.iter().filter(|b| b.count > 0).count()
+        )?;
+        for (line, count) in &self.lines {
+            writeln!(w, "DA:{line},{count}")?;
+        }
+        writeln!(w, "LF:{}", self.lines.len())?;
+        writeln!(w, "LH:{}", self.lines.values().filter(|&&c| c > 0).count())?;
+        writeln!(w, "end_of_record")?;
+        Ok(())
+    }
+}
+
+/// Coverage remapped to the original files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Returns the coverage of all original files, sorted by path.
+    pub fn files(&self) -> &[FileCoverage] {
+        &self.files
+    }
+
+    /// Returns the coverage of a single original file.
+    pub fn get_file(&self, path: &str) -> Option<&FileCoverage> {
+        self.files.iter().find(|file| file.path == path)
+    }
+
+    /// Converts the report into an Istanbul coverage map.
+    pub fn to_istanbul(&self) -> Value {
+        Value::Object(
+            self.files
+                .iter()
+                .map(|file| (file.path.clone(), file.to_istanbul()))
+                .collect(),
+        )
+    }
+
+    /// Writes the report as Istanbul JSON.
+    pub fn to_istanbul_writer<W: Write>(&self, w: W) -> Result<()> {
+        serde_json::to_writer(w, &self.to_istanbul())?;
+        Ok(())
+    }
+
+    /// Writes the report in lcov format.
+    pub fn to_lcov_writer<W: Write>(&self, mut w: W) -> Result<()> {
+        for file in &self.files {
+            file.write_lcov(&mut w)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps the coverage of a generated file to the original sources.
+///
+/// `generated` is the source of the generated file the coverage was
+/// recorded for and `sm` its sourcemap.  A line is counted with the highest
+/// count of the innermost V8 ranges covering the tokens mapped to it.  The
+/// top level function of the script is not reported as a function.
+pub fn remap_coverage(
+    coverage: &ScriptCoverage,
+    generated: &SourceView,
+    sm: &SourceMap,
+) -> CoverageReport {
+    let mut files: BTreeMap<u32, FileCoverage> = BTreeMap::new();
+
+    let map_offset = |offset: u32| {
+        let (line, col) = generated.offset_to_position(offset);
+        let token = sm.lookup_token(line, col).filter(|t| t.has_source())?;
+        Some((token.get_src_id(), token.get_src()))
+    };
+    let map_range = |range: &CoverageRange| {
+        let (src_id, start) = map_offset(range.start_offset)?;
+        // fall back to the start if the end points into a different source
+        let end = match map_offset(range.end_offset.saturating_sub(1).max(range.start_offset)) {
+            Some((end_src_id, end)) if end_src_id == src_id && end >= start => end,
+            _ => start,
+        };
+        Some((src_id, start, end))
+    };
+
+    let mut ranges: Vec<CoverageRange> = vec![];
+    for func in &coverage.functions {
+        ranges.extend(func.ranges.iter().copied());
+
+        let mut func_ranges = func.ranges.iter();
+        if let Some(range) = func_ranges.next() {
+            let is_top_level = func.function_name.is_empty() && range.start_offset == 0;
+            if !is_top_level {
+                if let Some((src_id, start, end)) = map_range(range) {
+                    file_entry(&mut files, sm, src_id)
+                        .functions
+                        .push(FunctionEntry {
+                            name: func.function_name.clone(),
+                            start,
+                            end,
+                            count: range.count,
+                        });
+                }
+            }
+        }
+
+        if func.is_block_coverage {
+            for range in func_ranges {
+                if let Some((src_id, start, end)) = map_range(range) {
+                    file_entry(&mut files, sm, src_id)
+                        .branches
+                        .push(BranchEntry {
+                            start,
+                            end,
+                            count: range.count,
+                        });
+                }
+            }
+        }
+    }
+
+    // Assign every token the count of the innermost range containing it.
+    // V8 ranges are properly nested so a stack is enough.
+    ranges.sort_by_key(|r| (r.start_offset, std::cmp::Reverse(r.end_offset)));
+    let mut next_range = 0;
+    let mut stack: Vec<CoverageRange> = vec![];
+    for token in sm.tokens() {
+        let offset = match generated.position_to_offset(token.get_dst_line(), token.get_dst_col()) {
+            Some(offset) => offset,
+            None => break,
+        };
+        while let Some(range) = ranges.get(next_range).filter(|r| r.start_offset <= offset) {
+            while stack
+                .last()
+                .is_some_and(|top| top.end_offset <= range.start_offset)
+            {
+                stack.pop();
+            }
+            stack.push(*range);
+            next_range += 1;
+        }
+        while stack.last().is_some_and(|top| top.end_offset <= offset) {
+            stack.pop();
+        }
+
+        let count = match stack.last() {
+            Some(range) if token.has_source() => range.count,
+            _ => continue,
+        };
+        let line = file_entry(&mut files, sm, token.get_src_id())
+            .lines
+            .entry(token.get_src_line() + 1)
+            .or_insert(0);
+        *line = (*line).max(count);
+    }
+
+    let mut files: Vec<_> = files.into_values().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    CoverageReport { files }
+}
+
+fn file_entry<'a>(
+    files: &'a mut BTreeMap<u32, FileCoverage>,
+    sm: &SourceMap,
+    src_id: u32,
+) -> &'a mut FileCoverage {
+    files
+        .entry(src_id)
+        .or_insert_with(|| FileCoverage::new(sm.get_source(src_id).unwrap_or_default()))
+}
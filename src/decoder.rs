@@ -1,15 +1,26 @@
+use std::borrow::Cow;
+use std::fmt;
 use std::io;
 use std::io::{BufReader, Read};
+use std::sync::Arc;
 
 use bitvec::field::BitField;
 use bitvec::order::Lsb0;
 use bitvec::vec::BitVec;
+use debugid::DebugId;
+use serde::Deserialize;
+use serde_json::value::RawValue;
 use serde_json::Value;
 
 use crate::errors::{Error, Result};
 use crate::hermes::decode_hermes;
-use crate::jsontypes::RawSourceMap;
+use crate::jsontypes::{
+    parse_json_file, parse_json_name, parse_json_str, BorrowedRawSection, BorrowedRawSourceMap,
+    RawSection, RawSourceMap,
+};
+use crate::scopes::{decode_generated_ranges, decode_original_scope};
 use crate::types::{DecodedMap, RawToken, SourceMap, SourceMapIndex, SourceMapSection};
+use crate::validate::validate_raw_order;
 use crate::vlq::parse_vlq_segment_into;
 
 #[derive(PartialEq, Eq)]
@@ -144,95 +155,318 @@ fn decode_rmi(rmi_str: &str, val: &mut BitVec<u8, Lsb0>) -> Result<()> {
     Ok(())
 }
 
-pub fn decode_regular(rsm: RawSourceMap) -> Result<SourceMap> {
-    let mut dst_col;
-
+/// An iterator that decodes the `mappings` of a sourcemap one token at a time.
+///
+/// This lets you walk the tokens of very large sourcemaps without keeping
+/// all of them in memory.  Tokens are yielded in the order they appear in
+/// the mappings, which is sorted by line but not necessarily by column.
+///
+/// ```rust
+/// use sourcemap::MappingsIter;
+/// let mut iter = MappingsIter::new("AAAA,GAAIA;CACR", None, 1, 1);
+/// let token = iter.nth(1).unwrap().unwrap();
+/// assert_eq!((token.dst_line, token.dst_col), (0, 3));
+/// assert_eq!(token.name_id, 0);
+/// ```
+pub struct MappingsIter<'a> {
+    lines: std::str::Split<'a, char>,
+    range_lines: std::str::Split<'a, char>,
+    segments: Option<std::str::Split<'a, char>>,
+    segment_idx: usize,
+    next_line: u32,
+    dst_line: u32,
+    dst_col: u32,
     // Source IDs, lines, columns, and names are "running" values.
     // Each token (except the first) contains the delta from the previous value.
-    let mut running_src_id = 0;
-    let mut running_src_line = 0;
-    let mut running_src_col = 0;
-    let mut running_name_id = 0;
+    src_id: u32,
+    src_line: u32,
+    src_col: u32,
+    name_id: u32,
+    source_count: u32,
+    name_count: u32,
+    rmi: BitVec<u8, Lsb0>,
+    nums: Vec<i64>,
+    failed: bool,
+    lenient: bool,
+}
 
-    let names = rsm.names.unwrap_or_default();
-    let sources = rsm.sources.unwrap_or_default();
-    let range_mappings = rsm.range_mappings.unwrap_or_default();
-    let mappings = rsm.mappings.unwrap_or_default();
-    let allocation_size = mappings.matches(&[',', ';'][..]).count() + 10;
-    let mut tokens = Vec::with_capacity(allocation_size);
+impl<'a> MappingsIter<'a> {
+    /// Creates a new iterator over the given `mappings` and optional
+    /// `rangeMappings` strings.
+    ///
+    /// The number of sources and names is used to validate references.
+    pub fn new(
+        mappings: &'a str,
+        range_mappings: Option<&'a str>,
+        source_count: u32,
+        name_count: u32,
+    ) -> MappingsIter<'a> {
+        MappingsIter {
+            lines: mappings.split(';'),
+            range_lines: range_mappings.unwrap_or_default().split(';'),
+            segments: None,
+            segment_idx: 0,
+            next_line: 0,
+            dst_line: 0,
+            dst_col: 0,
+            src_id: 0,
+            src_line: 0,
+            src_col: 0,
+            name_id: 0,
+            source_count,
+            name_count,
+            rmi: BitVec::new(),
+            nums: Vec::with_capacity(6),
+            failed: false,
+            lenient: false,
+        }
+    }
 
-    let mut nums = Vec::with_capacity(6);
-    let mut rmi = BitVec::new();
+    /// Continues decoding at a later line.
+    ///
+    /// The strings passed to [`MappingsIter::new`] must start at that line
+    /// and `state` holds the running source id, source line, source column
+    /// and name id after the last segment of the previous lines.
+    pub(crate) fn resume_at(mut self, line: u32, state: (u32, u32, u32, u32)) -> MappingsIter<'a> {
+        self.next_line = line;
+        (self.src_id, self.src_line, self.src_col, self.name_id) = state;
+        self
+    }
 
-    for (dst_line, (line, rmi_str)) in mappings
-        .split(';')
-        .zip(range_mappings.split(';').chain(std::iter::repeat("")))
-        .enumerate()
-    {
-        if line.is_empty() {
-            continue;
-        }
+    /// Keeps decoding after errors instead of stopping at the first one.
+    ///
+    /// Malformed segments are still reported as errors but skipped.
+    pub(crate) fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
 
-        dst_col = 0;
+    /// Returns the line and the index within the line of the segment that
+    /// was decoded last.
+    pub(crate) fn last_segment(&self) -> (u32, u32) {
+        (self.dst_line, self.segment_idx.saturating_sub(1) as u32)
+    }
 
-        decode_rmi(rmi_str, &mut rmi)?;
+    fn decode_segment(&mut self, segment: &str, segment_idx: usize) -> Result<RawToken> {
+        let nums = &mut self.nums;
+        nums.clear();
+        parse_vlq_segment_into(segment, nums)?;
 
-        for (line_index, segment) in line.split(',').enumerate() {
-            if segment.is_empty() {
-                continue;
+        // The column delta is applied even to malformed segments so that
+        // lenient decoding keeps the columns of the following segments.
+        if let Some(&dst_col_delta) = nums.first() {
+            self.dst_col = (i64::from(self.dst_col) + dst_col_delta) as u32;
+        }
+        match nums.len() {
+            1 | 4 | 5 => {}
+            _ => return Err(Error::BadSegmentSize(nums.len() as u32)),
+        }
+
+        // The source file , source line, source column, and name
+        // may not be present in the current token. We use `u32::MAX`
+        // as the placeholder for missing values.
+        let mut current_src_id = !0;
+        let mut current_src_line = !0;
+        let mut current_src_col = !0;
+        let mut current_name_id = !0;
+
+        if nums.len() > 1 {
+            // All deltas are applied before validating the references so
+            // that lenient decoding stays in sync after a bad segment.
+            self.src_id = (i64::from(self.src_id) + nums[1]) as u32;
+            self.src_line = (i64::from(self.src_line) + nums[2]) as u32;
+            self.src_col = (i64::from(self.src_col) + nums[3]) as u32;
+            if nums.len() > 4 {
+                self.name_id = (i64::from(self.name_id) + nums[4]) as u32;
             }
 
-            nums.clear();
-            parse_vlq_segment_into(segment, &mut nums)?;
-            match nums.len() {
-                1 | 4 | 5 => {}
-                _ => return Err(Error::BadSegmentSize(nums.len() as u32)),
+            if self.src_id >= self.source_count {
+                return Err(Error::BadSourceReference(self.src_id));
             }
 
-            dst_col = (i64::from(dst_col) + nums[0]) as u32;
+            current_src_id = self.src_id;
+            current_src_line = self.src_line;
+            current_src_col = self.src_col;
 
-            // The source file , source line, source column, and name
-            // may not be present in the current token. We use `u32::MAX`
-            // as the placeholder for missing values.
-            let mut current_src_id = !0;
-            let mut current_src_line = !0;
-            let mut current_src_col = !0;
-            let mut current_name_id = !0;
+            if nums.len() > 4 {
+                if self.name_id >= self.name_count {
+                    return Err(Error::BadNameReference(self.name_id));
+                }
+                current_name_id = self.name_id;
+            }
+        }
 
-            if nums.len() > 1 {
-                running_src_id = (i64::from(running_src_id) + nums[1]) as u32;
+        let is_range = self.rmi.get(segment_idx).map(|v| *v).unwrap_or_default();
 
-                if running_src_id >= sources.len() as u32 {
-                    return Err(Error::BadSourceReference(running_src_id));
-                }
+        Ok(RawToken {
+            dst_line: self.dst_line,
+            dst_col: self.dst_col,
+            src_line: current_src_line,
+            src_col: current_src_col,
+            src_id: current_src_id,
+            name_id: current_name_id,
+            is_range,
+        })
+    }
+}
 
-                running_src_line = (i64::from(running_src_line) + nums[2]) as u32;
-                running_src_col = (i64::from(running_src_col) + nums[3]) as u32;
+impl Iterator for MappingsIter<'_> {
+    type Item = Result<RawToken>;
 
-                current_src_id = running_src_id;
-                current_src_line = running_src_line;
-                current_src_col = running_src_col;
+    fn next(&mut self) -> Option<Result<RawToken>> {
+        loop {
+            if self.failed {
+                return None;
+            }
 
-                if nums.len() > 4 {
-                    running_name_id = (i64::from(running_name_id) + nums[4]) as u32;
-                    if running_name_id >= names.len() as u32 {
-                        return Err(Error::BadNameReference(running_name_id));
-                    }
-                    current_name_id = running_name_id;
+            if let Some(segment) = self.segments.as_mut().and_then(Iterator::next) {
+                let segment_idx = self.segment_idx;
+                self.segment_idx += 1;
+                if segment.is_empty() {
+                    continue;
                 }
+                let rv = self.decode_segment(segment, segment_idx);
+                self.failed = rv.is_err() && !self.lenient;
+                return Some(rv);
             }
 
-            let is_range = rmi.get(line_index).map(|v| *v).unwrap_or_default();
-
-            tokens.push(RawToken {
-                dst_line: dst_line as u32,
-                dst_col,
-                src_line: current_src_line,
-                src_col: current_src_col,
-                src_id: current_src_id,
-                name_id: current_name_id,
-                is_range,
-            });
+            let line = self.lines.next()?;
+            let rmi_str = self.range_lines.next().unwrap_or_default();
+            self.dst_line = self.next_line;
+            self.next_line += 1;
+            self.dst_col = 0;
+            self.segment_idx = 0;
+            self.segments = None;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let rmi = decode_rmi(rmi_str, &mut self.rmi);
+            self.segments = Some(line.split(','));
+            if let Err(err) = rmi {
+                // lenient decoding drops the range mappings of the line
+                self.rmi.clear();
+                self.failed = !self.lenient;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// Controls how sourcemaps are decoded by [`decode_with_options`].
+///
+/// Default configuration:
+///
+/// * `lenient`: false
+/// * `with_source_contents`: true
+/// * `with_names`: true
+/// * `with_range_mappings`: true
+/// * `with_facebook_extensions`: true
+/// * `max_tokens`: None
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// If enabled, malformed mapping segments are skipped and reported as
+    /// [`DecodeWarning`]s instead of failing the entire decode.
+    pub lenient: bool,
+    /// If enabled, `sourcesContent` is decoded.
+    pub with_source_contents: bool,
+    /// If enabled, `names` are decoded.  Without names tokens carry no
+    /// name and scope information cannot be decoded.
+    pub with_names: bool,
+    /// If enabled, `rangeMappings` are decoded.
+    pub with_range_mappings: bool,
+    /// If enabled, the `x_facebook_*` and `x_metro_*` extensions are
+    /// decoded.  Without them Hermes sourcemaps are decoded as regular
+    /// sourcemaps.
+    pub with_facebook_extensions: bool,
+    /// The maximum number of tokens a sourcemap may have.  Decoding fails
+    /// with [`Error::TooManyTokens`] if it has more.  For index maps the
+    /// limit applies to every section.
+    pub max_tokens: Option<usize>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> DecodeOptions {
+        DecodeOptions {
+            lenient: false,
+            with_source_contents: true,
+            with_names: true,
+            with_range_mappings: true,
+            with_facebook_extensions: true,
+            max_tokens: None,
+        }
+    }
+}
+
+/// A malformed mapping segment that was skipped by lenient decoding.
+#[derive(Debug)]
+pub struct DecodeWarning {
+    /// the 0-indexed generated line of the segment
+    pub line: u32,
+    /// the 0-indexed position of the segment within its line
+    pub segment: u32,
+    /// the error the segment caused
+    pub error: Error,
+}
+
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "skipped segment {} on line {}: {}",
+            self.segment, self.line, self.error
+        )
+    }
+}
+
+pub fn decode_regular(
+    rsm: RawSourceMap,
+    options: &DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<SourceMap> {
+    let names = rsm.names.filter(|_| options.with_names).unwrap_or_default();
+    let sources = rsm.sources.unwrap_or_default();
+    let mappings = rsm.mappings.unwrap_or_default();
+    let mut allocation_size = mappings.matches(&[',', ';'][..]).count() + 10;
+    if let Some(max_tokens) = options.max_tokens {
+        allocation_size = allocation_size.min(max_tokens);
+    }
+    let mut tokens = Vec::with_capacity(allocation_size);
+
+    let mut iter = MappingsIter::new(
+        &mappings,
+        rsm.range_mappings
+            .as_deref()
+            .filter(|_| options.with_range_mappings),
+        sources.len() as u32,
+        // name references cannot be validated if the names are skipped
+        if options.with_names {
+            names.len() as u32
+        } else {
+            !0
+        },
+    );
+    iter.set_lenient(options.lenient);
+    while let Some(token) = iter.next() {
+        match token {
+            Ok(mut token) => {
+                if let Some(max_tokens) = options.max_tokens.filter(|&max| tokens.len() >= max) {
+                    return Err(Error::TooManyTokens(max_tokens));
+                }
+                if !options.with_names {
+                    token.name_id = !0;
+                }
+                tokens.push(token);
+            }
+            Err(error) if options.lenient => {
+                let (line, segment) = iter.last_segment();
+                warnings.push(DecodeWarning {
+                    line,
+                    segment,
+                    error,
+                });
+            }
+            Err(err) => return Err(err),
         }
     }
 
@@ -253,20 +487,50 @@ pub fn decode_regular(rsm: RawSourceMap) -> Result<SourceMap> {
         })
         .collect::<Vec<_>>();
 
-    // file sometimes is not a string for unexplicable reasons
-    let file = rsm.file.map(|val| match val {
-        Value::String(s) => s.into(),
-        _ => "<invalid>".into(),
-    });
+    let file = rsm.file.map(|file| parse_json_file(file).into());
 
     let source_content = rsm
         .sources_content
+        .filter(|_| options.with_source_contents)
         .map(|x| x.into_iter().map(|v| v.map(Into::into)).collect::<Vec<_>>());
 
     let mut sm = SourceMap::new(file, tokens, names, sources, source_content);
     sm.set_source_root(rsm.source_root);
+
+    // malformed scope information is dropped instead of failing the
+    // entire sourcemap, just like hermes function maps.
+    let original_scopes = rsm.original_scopes.unwrap_or_default();
+    for (src_id, scope) in original_scopes.iter().enumerate() {
+        if src_id >= sm.get_source_count() as usize {
+            break;
+        }
+        let scope = scope
+            .as_deref()
+            .and_then(|scope| decode_original_scope(scope, &sm.names));
+        if scope.is_some() {
+            sm.set_original_scope(src_id as u32, scope);
+        }
+    }
+    if let Some(ranges) = rsm
+        .generated_ranges
+        .and_then(|ranges| decode_generated_ranges(&ranges, &sm.names, sm.get_source_count()))
+    {
+        sm.set_generated_ranges(ranges);
+    }
     sm.set_debug_id(rsm.debug_id.into());
-    if let Some(ignore_list) = rsm.ignore_list {
+    sm.extensions = rsm.extensions.into();
+    // the RAM bundle extensions are only understood on index maps
+    if let Some(offsets) = rsm.x_facebook_offsets {
+        sm.extensions
+            .fields
+            .insert("x_facebook_offsets".into(), serde_json::to_value(offsets)?);
+    }
+    if let Some(paths) = rsm.x_metro_module_paths {
+        sm.extensions
+            .fields
+            .insert("x_metro_module_paths".into(), serde_json::to_value(paths)?);
+    }
+    if let Some(ignore_list) = rsm.ignore_list.or(rsm.x_google_ignore_list) {
         for idx in ignore_list {
             sm.add_to_ignore_list(idx);
         }
@@ -275,44 +539,133 @@ pub fn decode_regular(rsm: RawSourceMap) -> Result<SourceMap> {
     Ok(sm)
 }
 
-fn decode_index(rsm: RawSourceMap) -> Result<SourceMapIndex> {
+fn decode_index(
+    rsm: RawSourceMap,
+    options: &DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<SourceMapIndex> {
     let mut sections = vec![];
 
     for mut raw_section in rsm.sections.unwrap_or_default() {
-        sections.push(SourceMapSection::new(
-            (raw_section.offset.line, raw_section.offset.column),
-            raw_section.url,
-            match raw_section.map.take() {
-                Some(map) => Some(decode_common(*map)?),
-                None => None,
-            },
-        ));
+        let offset = (raw_section.offset.line, raw_section.offset.column);
+        let map = match raw_section.map.take() {
+            Some(map) => {
+                let mut section_warnings = vec![];
+                let map = decode_common(*map, options, &mut section_warnings)?;
+                warnings.extend(section_warnings.into_iter().map(|mut warning| {
+                    warning.line += offset.0;
+                    warning
+                }));
+                Some(map)
+            }
+            None => None,
+        };
+        sections.push(SourceMapSection::new(offset, raw_section.url, map));
     }
 
     sections.sort_by_key(SourceMapSection::get_offset);
 
-    // file sometimes is not a string for unexplicable reasons
-    let file = rsm.file.map(|val| match val {
-        Value::String(s) => s,
-        _ => "<invalid>".into(),
-    });
+    let file = rsm.file.map(parse_json_file);
+
+    let (x_facebook_offsets, x_metro_module_paths) = if options.with_facebook_extensions {
+        (rsm.x_facebook_offsets, rsm.x_metro_module_paths)
+    } else {
+        (None, None)
+    };
 
     Ok(SourceMapIndex::new_ram_bundle_compatible(
         file,
         sections,
-        rsm.x_facebook_offsets,
-        rsm.x_metro_module_paths,
+        x_facebook_offsets,
+        x_metro_module_paths,
     )
-    .with_debug_id(rsm.debug_id.into()))
+    .with_debug_id(rsm.debug_id.into())
+    .with_extensions(rsm.extensions.into()))
 }
 
-fn decode_common(rsm: RawSourceMap) -> Result<DecodedMap> {
+fn decode_common(
+    rsm: RawSourceMap,
+    options: &DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<DecodedMap> {
     Ok(if rsm.sections.is_some() {
-        DecodedMap::Index(decode_index(rsm)?)
-    } else if rsm.x_facebook_sources.is_some() {
-        DecodedMap::Hermes(decode_hermes(rsm)?)
+        DecodedMap::Index(decode_index(rsm, options, warnings)?)
+    } else if rsm.x_facebook_sources.is_some() && options.with_facebook_extensions {
+        DecodedMap::Hermes(decode_hermes(rsm, options, warnings)?)
     } else {
-        DecodedMap::Regular(decode_regular(rsm)?)
+        DecodedMap::Regular(decode_regular(rsm, options, warnings)?)
+    })
+}
+
+/// The values of a regular sourcemap that borrow from the input buffer.
+///
+/// Shared by `BorrowedSourceMap` and `StreamingSourceMap`.
+pub(crate) struct BorrowedParts<'a> {
+    pub file: Option<String>,
+    pub source_root: Option<Arc<str>>,
+    pub sources: Vec<Cow<'a, str>>,
+    pub sources_prefixed: Option<Vec<Arc<str>>>,
+    pub sources_content: Vec<&'a RawValue>,
+    pub names: Vec<Cow<'a, str>>,
+    pub mappings: Cow<'a, str>,
+    pub range_mappings: Option<Cow<'a, str>>,
+    pub ignore_list: Vec<u32>,
+    pub debug_id: Option<DebugId>,
+}
+
+/// Decodes everything but the mappings and the source contents of a
+/// regular sourcemap without copying strings that are not escaped.
+///
+/// Index maps are rejected with `IncompatibleSourceMap`.
+pub(crate) fn decode_borrowed_parts(slice: &[u8]) -> Result<BorrowedParts<'_>> {
+    let content = std::str::from_utf8(strip_junk_header(slice)?)?;
+    let rsm: BorrowedRawSourceMap<'_> = serde_json::from_str(content)?;
+    if rsm.sections.is_some() {
+        return Err(Error::IncompatibleSourceMap);
+    }
+
+    let sources = rsm
+        .sources
+        .unwrap_or_default()
+        .into_iter()
+        .map(|raw| Ok(parse_json_str(raw)?.unwrap_or_default()))
+        .collect::<Result<Vec<_>>>()?;
+    let source_root: Option<Arc<str>> = rsm.source_root.map(Into::into);
+    let sources_prefixed = source_root
+        .as_deref()
+        .filter(|rs| !rs.is_empty())
+        .map(|source_root| {
+            sources
+                .iter()
+                .map(|source| SourceMap::prefix_source(source_root, source))
+                .collect()
+        });
+
+    Ok(BorrowedParts {
+        file: rsm.file.map(parse_json_file),
+        source_root,
+        sources,
+        sources_prefixed,
+        sources_content: rsm.sources_content.unwrap_or_default(),
+        names: rsm
+            .names
+            .unwrap_or_default()
+            .into_iter()
+            .map(parse_json_name)
+            .collect(),
+        mappings: match rsm.mappings {
+            Some(raw) => parse_json_str(raw)?.unwrap_or_default(),
+            None => Cow::Borrowed(""),
+        },
+        range_mappings: match rsm.range_mappings {
+            Some(raw) => parse_json_str(raw)?,
+            None => None,
+        },
+        ignore_list: rsm
+            .ignore_list
+            .or(rsm.x_google_ignore_list)
+            .unwrap_or_default(),
+        debug_id: rsm.debug_id.or(rsm.legacy_debug_id),
     })
 }
 
@@ -324,7 +677,7 @@ pub fn decode<R: Read>(rdr: R) -> Result<DecodedMap> {
     let mut rdr = StripHeaderReader::new(rdr);
     let mut rdr = BufReader::new(&mut rdr);
     let rsm: RawSourceMap = serde_json::from_reader(&mut rdr)?;
-    decode_common(rsm)
+    decode_common(rsm, &DecodeOptions::default(), &mut vec![])
 }
 
 /// Decodes a sourcemap or sourcemap index from a byte slice
@@ -334,7 +687,145 @@ pub fn decode<R: Read>(rdr: R) -> Result<DecodedMap> {
 pub fn decode_slice(slice: &[u8]) -> Result<DecodedMap> {
     let content = strip_junk_header(slice)?;
     let rsm: RawSourceMap = serde_json::from_slice(content)?;
-    decode_common(rsm)
+    decode_common(rsm, &DecodeOptions::default(), &mut vec![])
+}
+
+/// Parses the optional values of a lazily parsed sourcemap that are needed
+/// for the given options.
+fn parse_lazy(slice: &[u8], options: &DecodeOptions) -> Result<RawSourceMap> {
+    fn parse<'a, T: Deserialize<'a>>(
+        raw: Option<&'a RawValue>,
+        enabled: bool,
+    ) -> Result<Option<T>> {
+        match raw.filter(|_| enabled) {
+            Some(raw) => Ok(serde_json::from_str(raw.get())?),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_strs(raw: Vec<&RawValue>) -> Result<Vec<Option<String>>> {
+        raw.into_iter()
+            .map(|raw| Ok(parse_json_str(raw)?.map(Cow::into_owned)))
+            .collect()
+    }
+
+    let lazy: BorrowedRawSourceMap<'_> = serde_json::from_slice(slice)?;
+    let sections = match lazy.sections {
+        Some(raw) => Some(
+            serde_json::from_str::<Vec<BorrowedRawSection<'_>>>(raw.get())?
+                .into_iter()
+                .map(|section| {
+                    let map = match section.map {
+                        Some(map) => Some(Box::new(parse_lazy(map.get().as_bytes(), options)?)),
+                        None => None,
+                    };
+                    Ok(RawSection {
+                        offset: section.offset,
+                        url: section.url,
+                        map,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        ),
+        None => None,
+    };
+    let sources_content = match lazy.sources_content {
+        Some(raw) if options.with_source_contents => Some(parse_strs(raw)?),
+        _ => None,
+    };
+    let names = match lazy.names {
+        Some(raw) if options.with_names => Some(
+            raw.into_iter()
+                .map(|raw| serde_json::from_str(raw.get()))
+                .collect::<serde_json::Result<_>>()?,
+        ),
+        _ => None,
+    };
+    let mappings = match lazy.mappings {
+        Some(raw) => parse_json_str(raw)?.map(Cow::into_owned),
+        None => None,
+    };
+    let extensions = options.with_facebook_extensions;
+
+    Ok(RawSourceMap {
+        version: lazy.version,
+        file: lazy.file,
+        sources: lazy.sources.map(parse_strs).transpose()?,
+        source_root: lazy.source_root,
+        sources_content,
+        sections,
+        names,
+        range_mappings: parse(lazy.range_mappings, options.with_range_mappings)?,
+        mappings,
+        ignore_list: lazy.ignore_list,
+        x_google_ignore_list: lazy.x_google_ignore_list,
+        original_scopes: parse(lazy.original_scopes, true)?,
+        generated_ranges: parse(lazy.generated_ranges, true)?,
+        x_facebook_offsets: parse(lazy.x_facebook_offsets, extensions)?,
+        x_metro_module_paths: parse(lazy.x_metro_module_paths, extensions)?,
+        x_facebook_sources: parse(lazy.x_facebook_sources, extensions)?,
+        debug_id: lazy.debug_id.or(lazy.legacy_debug_id).into(),
+        extensions: lazy.extensions,
+    })
+}
+
+/// Decodes a sourcemap or sourcemap index from a byte slice with options
+///
+/// Values that are skipped by the options are not parsed at all which
+/// saves memory and time if only some information is needed.  Returns the
+/// decoded map together with the problems that were skipped because of
+/// [`DecodeOptions::lenient`].
+///
+/// ```rust
+/// use sourcemap::{decode_with_options, DecodeOptions};
+/// let input: &[_] = br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA,CCAA,CDAA"}"#;
+/// let options = DecodeOptions {
+///     lenient: true,
+///     ..Default::default()
+/// };
+/// let (map, warnings) = decode_with_options(input, &options).unwrap();
+/// assert_eq!(map.lookup_token(0, 2).unwrap().get_dst_col(), 2);
+/// assert_eq!((warnings[0].line, warnings[0].segment), (0, 1));
+/// ```
+pub fn decode_with_options(
+    slice: &[u8],
+    options: &DecodeOptions,
+) -> Result<(DecodedMap, Vec<DecodeWarning>)> {
+    let content = strip_junk_header(slice)?;
+    let rsm = parse_lazy(content, options)?;
+    let mut warnings = vec![];
+    let map = decode_common(rsm, options, &mut warnings)?;
+    Ok((map, warnings))
+}
+
+/// Decodes a sourcemap or sourcemap index from a byte slice and validates it
+///
+/// This fails with [`Error::ValidationFailed`] listing all problems if the
+/// sourcemap does not pass [`DecodedMap::validate`] or if its mappings are
+/// not sorted.
+///
+/// ```rust
+/// use sourcemap::{decode_strict, DiagnosticKind, Error};
+/// let input: &[_] = br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"EAAA,DAAA"}"#;
+/// match decode_strict(input) {
+///     Err(Error::ValidationFailed(diagnostics)) => {
+///         assert_eq!(diagnostics[0].kind, DiagnosticKind::UnsortedMapping);
+///     }
+///     _ => panic!("expected validation to fail"),
+/// }
+/// ```
+pub fn decode_strict(slice: &[u8]) -> Result<DecodedMap> {
+    let content = strip_junk_header(slice)?;
+    let rsm: RawSourceMap = serde_json::from_slice(content)?;
+    let mut diagnostics = vec![];
+    validate_raw_order(&rsm, (0, 0), &mut diagnostics);
+    let map = decode_common(rsm, &DecodeOptions::default(), &mut vec![])?;
+    diagnostics.extend(map.validate());
+    if diagnostics.is_empty() {
+        Ok(map)
+    } else {
+        Err(Error::ValidationFailed(diagnostics))
+    }
 }
 
 /// Loads a sourcemap from a data URL.
@@ -422,13 +913,18 @@ mod tests {
             range_mappings: None,
             mappings: None,
             ignore_list: None,
+            x_google_ignore_list: None,
+            original_scopes: None,
+            generated_ranges: None,
             x_facebook_offsets: None,
             x_metro_module_paths: None,
             x_facebook_sources: None,
             debug_id: None.into(),
+            extensions: Default::default(),
         };
 
-        let decoded = decode_common(raw).expect("should decoded");
+        let decoded =
+            decode_common(raw, &DecodeOptions::default(), &mut vec![]).expect("should decoded");
         assert_eq!(
             decoded,
             DecodedMap::Index(SourceMapIndex::new(Some("test.js".into()), vec![]))
@@ -450,13 +946,18 @@ mod tests {
             range_mappings: None,
             mappings: None,
             ignore_list: None,
+            x_google_ignore_list: None,
+            original_scopes: None,
+            generated_ranges: None,
             x_facebook_offsets: None,
             x_metro_module_paths: None,
             x_facebook_sources: None,
             debug_id: Some(DEBUG_ID.parse().expect("valid debug id")).into(),
+            extensions: Default::default(),
         };
 
-        let decoded = decode_common(raw).expect("should decode");
+        let decoded =
+            decode_common(raw, &DecodeOptions::default(), &mut vec![]).expect("should decode");
         assert_eq!(
             decoded,
             DecodedMap::Index(
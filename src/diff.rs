@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::types::SourceMap;
+
+/// The original position a generated position maps to.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginalPosition {
+    /// the source as returned by [`SourceMap::get_source`]
+    pub source: String,
+    /// the 0-indexed original line
+    pub line: u32,
+    /// the 0-indexed original column
+    pub col: u32,
+    /// the name of the token
+    pub name: Option<String>,
+}
+
+impl fmt::Display for OriginalPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.source, self.line, self.col)?;
+        if let Some(ref name) = self.name {
+            write!(f, " name={name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The kind of a [`MappingDiff`].
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MappingChange {
+    /// the generated position is only mapped in the new sourcemap
+    Added,
+    /// the generated position is only mapped in the old sourcemap
+    Removed,
+    /// the generated position maps to a different original position or name
+    Changed,
+}
+
+/// A generated position that is mapped differently by two sourcemaps.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingDiff {
+    /// the 0-indexed generated line
+    pub dst_line: u32,
+    /// the 0-indexed generated column
+    pub dst_col: u32,
+    /// the original position in the old sourcemap
+    pub old: Option<OriginalPosition>,
+    /// the original position in the new sourcemap
+    pub new: Option<OriginalPosition>,
+}
+
+impl MappingDiff {
+    /// Returns the kind of the difference.
+    pub fn change(&self) -> MappingChange {
+        match (&self.old, &self.new) {
+            (None, _) => MappingChange::Added,
+            (_, None) => MappingChange::Removed,
+            _ => MappingChange::Changed,
+        }
+    }
+}
+
+impl fmt::Display for MappingDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.dst_line, self.dst_col)?;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{old} -> {new}"),
+            (Some(old), None) => write!(f, "- {old}"),
+            (None, Some(new)) => write!(f, "+ {new}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// The semantic differences between two sourcemaps.
+///
+/// Returned by [`diff`].  Sources and names are compared by value, so
+/// reordering the `sources` or `names` of a sourcemap does not produce a
+/// difference.  The `Display` implementation prints one difference per
+/// line, which makes it useful in test assertions.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMapDiff {
+    /// the mappings that differ, sorted by generated position
+    pub mappings: Vec<MappingDiff>,
+    /// the sources only listed by the new sourcemap
+    pub added_sources: Vec<String>,
+    /// the sources only listed by the old sourcemap
+    pub removed_sources: Vec<String>,
+    /// the sources listed by both sourcemaps with different source contents
+    pub changed_contents: Vec<String>,
+}
+
+impl SourceMapDiff {
+    /// Returns `true` if the sourcemaps are semantically equal.
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+            && self.added_sources.is_empty()
+            && self.removed_sources.is_empty()
+            && self.changed_contents.is_empty()
+    }
+}
+
+impl fmt::Display for SourceMapDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for source in &self.added_sources {
+            writeln!(f, "+ source {source}")?;
+        }
+        for source in &self.removed_sources {
+            writeln!(f, "- source {source}")?;
+        }
+        for source in &self.changed_contents {
+            writeln!(f, "~ contents {source}")?;
+        }
+        for mapping in &self.mappings {
+            writeln!(f, "{mapping}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the original positions of a sourcemap by generated position.
+fn original_positions(sm: &SourceMap) -> BTreeMap<(u32, u32), OriginalPosition> {
+    let mut rv = BTreeMap::new();
+    for token in sm.tokens() {
+        if let Some(source) = token.get_source() {
+            rv.insert(
+                token.get_dst(),
+                OriginalPosition {
+                    source: source.to_string(),
+                    line: token.get_src_line(),
+                    col: token.get_src_col(),
+                    name: token.get_name().map(str::to_string),
+                },
+            );
+        }
+    }
+    rv
+}
+
+/// Collects the source contents of a sourcemap by source.
+fn source_contents(sm: &SourceMap) -> BTreeMap<&str, Option<&str>> {
+    let mut rv = BTreeMap::new();
+    for src_id in 0..sm.get_source_count() {
+        if let Some(source) = sm.get_source(src_id) {
+            rv.entry(source)
+                .or_insert_with(|| sm.get_source_contents(src_id));
+        }
+    }
+    rv
+}
+
+/// Computes the semantic differences between two sourcemaps.
+///
+/// Mappings are compared by generated position.  Tokens without a source
+/// are treated like missing tokens and when several tokens share a
+/// generated position the last one wins.  Sources are compared as returned
+/// by [`SourceMap::get_source`], that is with the `sourceRoot` applied.
+///
+/// ```rust
+/// use sourcemap::{diff, MappingChange, SourceMapBuilder};
+///
+/// let mut builder = SourceMapBuilder::new(None);
+/// builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+/// builder.add(0, 4, 1, 0, Some("a.js"), None, false);
+/// let old = builder.into_sourcemap();
+///
+/// let mut builder = SourceMapBuilder::new(None);
+/// builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+/// builder.add(0, 4, 2, 0, Some("a.js"), Some("foo"), false);
+/// let new = builder.into_sourcemap();
+///
+/// let changes = diff(&old, &new);
+/// assert_eq!(changes.mappings.len(), 1);
+/// assert_eq!(changes.mappings[0].change(), MappingChange::Changed);
+/// assert_eq!(changes.to_string(), "0:4: a.js:1:0 -> a.js:2:0 name=foo\n");
+/// assert!(diff(&old, &old).is_empty());
+/// ```
+pub fn diff(old: &SourceMap, new: &SourceMap) -> SourceMapDiff {
+    let mut rv = SourceMapDiff::default();
+
+    let mut old_positions = original_positions(old);
+    for (dst, new_pos) in original_positions(new) {
+        let old_pos = old_positions.remove(&dst);
+        if old_pos.as_ref() != Some(&new_pos) {
+            rv.mappings.push(MappingDiff {
+                dst_line: dst.0,
+                dst_col: dst.1,
+                old: old_pos,
+                new: Some(new_pos),
+            });
+        }
+    }
+    rv.mappings
+        .extend(old_positions.into_iter().map(|(dst, old_pos)| MappingDiff {
+            dst_line: dst.0,
+            dst_col: dst.1,
+            old: Some(old_pos),
+            new: None,
+        }));
+    rv.mappings
+        .sort_by_key(|mapping| (mapping.dst_line, mapping.dst_col));
+
+    let old_contents = source_contents(old);
+    let new_contents = source_contents(new);
+    for (source, contents) in &new_contents {
+        match old_contents.get(source) {
+            None => rv.added_sources.push(source.to_string()),
+            Some(old_contents) if old_contents != contents => {
+                rv.changed_contents.push(source.to_string())
+            }
+            Some(_) => {}
+        }
+    }
+    rv.removed_sources.extend(
+        old_contents
+            .keys()
+            .filter(|source| !new_contents.contains_key(*source))
+            .map(|source| source.to_string()),
+    );
+
+    rv
+}
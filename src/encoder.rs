@@ -7,6 +7,7 @@ use serde_json::Value;
 
 use crate::errors::Result;
 use crate::jsontypes::{RawSection, RawSectionOffset, RawSourceMap};
+use crate::scopes::encode_scopes;
 use crate::types::{DecodedMap, SourceMap, SourceMapIndex};
 use crate::vlq::encode_vlq;
 
@@ -14,8 +15,62 @@ pub trait Encodable {
     fn as_raw_sourcemap(&self) -> RawSourceMap;
 }
 
-pub fn encode<M: Encodable, W: Write>(sm: &M, mut w: W) -> Result<()> {
-    let ty = sm.as_raw_sourcemap();
+/// The key under which the ignore list of a sourcemap is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreListKey {
+    /// The `ignoreList` key of the sourcemap specification.
+    Standard,
+    /// The `x_google_ignoreList` key that older versions of Chrome DevTools
+    /// understand.
+    Legacy,
+    /// Both keys.
+    Both,
+}
+
+/// Controls how sourcemaps are written by the `to_writer_with_options`
+/// methods.
+///
+/// Default configuration:
+///
+/// * `ignore_list_key`: [`IgnoreListKey::Standard`]
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    /// The key the ignore list is written under.
+    pub ignore_list_key: IgnoreListKey,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> EncodeOptions {
+        EncodeOptions {
+            ignore_list_key: IgnoreListKey::Standard,
+        }
+    }
+}
+
+fn apply_options(rsm: &mut RawSourceMap, options: &EncodeOptions) {
+    match options.ignore_list_key {
+        IgnoreListKey::Standard => {}
+        IgnoreListKey::Legacy => rsm.x_google_ignore_list = rsm.ignore_list.take(),
+        IgnoreListKey::Both => rsm.x_google_ignore_list = rsm.ignore_list.clone(),
+    }
+    for section in rsm.sections.iter_mut().flatten() {
+        if let Some(ref mut map) = section.map {
+            apply_options(map, options);
+        }
+    }
+}
+
+pub fn encode<M: Encodable, W: Write>(sm: &M, w: W) -> Result<()> {
+    encode_with_options(sm, w, &EncodeOptions::default())
+}
+
+pub fn encode_with_options<M: Encodable, W: Write>(
+    sm: &M,
+    mut w: W,
+    options: &EncodeOptions,
+) -> Result<()> {
+    let mut ty = sm.as_raw_sourcemap();
+    apply_options(&mut ty, options);
     serde_json::to_writer(&mut w, &ty)?;
     Ok(())
 }
@@ -160,14 +215,16 @@ impl Encodable for SourceMap {
                 }
             })
             .collect();
-        RawSourceMap {
+        let mut names = self.names.clone();
+        let (original_scopes, generated_ranges) = encode_scopes(self, &mut names);
+        let mut rsm = RawSourceMap {
             version: Some(3),
             file: self.get_file().map(|x| Value::String(x.to_string())),
             sources: Some(self.sources.iter().map(|x| Some(x.to_string())).collect()),
             source_root: self.get_source_root().map(|x| x.to_string()),
             sources_content: if have_contents { Some(contents) } else { None },
             sections: None,
-            names: Some(self.names().map(|x| Value::String(x.to_string())).collect()),
+            names: Some(names.iter().map(|x| Value::String(x.to_string())).collect()),
             range_mappings: serialize_range_mappings(self),
             mappings: Some(serialize_mappings(self)),
             ignore_list: if self.ignore_list.is_empty() {
@@ -175,17 +232,23 @@ impl Encodable for SourceMap {
             } else {
                 Some(self.ignore_list.iter().cloned().collect())
             },
+            x_google_ignore_list: None,
+            original_scopes,
+            generated_ranges,
             x_facebook_offsets: None,
             x_metro_module_paths: None,
             x_facebook_sources: None,
             debug_id: self.get_debug_id().into(),
-        }
+            extensions: Default::default(),
+        };
+        rsm.set_extensions(&self.extensions.fields);
+        rsm
     }
 }
 
 impl Encodable for SourceMapIndex {
     fn as_raw_sourcemap(&self) -> RawSourceMap {
-        RawSourceMap {
+        let mut rsm = RawSourceMap {
             version: Some(3),
             file: self.get_file().map(|x| Value::String(x.to_string())),
             sources: None,
@@ -209,11 +272,17 @@ impl Encodable for SourceMapIndex {
             range_mappings: None,
             mappings: None,
             ignore_list: None,
-            x_facebook_offsets: None,
-            x_metro_module_paths: None,
+            x_google_ignore_list: None,
+            original_scopes: None,
+            generated_ranges: None,
+            x_facebook_offsets: self.x_facebook_offsets().map(<[_]>::to_vec),
+            x_metro_module_paths: self.x_metro_module_paths().map(<[_]>::to_vec),
             x_facebook_sources: None,
             debug_id: self.debug_id().into(),
-        }
+            extensions: Default::default(),
+        };
+        rsm.set_extensions(&self.extensions.fields);
+        rsm
     }
 }
 
@@ -272,10 +341,14 @@ mod tests {
                 range_mappings: None,
                 mappings: None,
                 ignore_list: None,
+                x_google_ignore_list: None,
+                original_scopes: None,
+                generated_ranges: None,
                 x_facebook_offsets: None,
                 x_metro_module_paths: None,
                 x_facebook_sources: None,
                 debug_id: None.into(),
+                extensions: Default::default(),
             }
         );
     }
@@ -301,10 +374,14 @@ mod tests {
                 range_mappings: None,
                 mappings: None,
                 ignore_list: None,
+                x_google_ignore_list: None,
+                original_scopes: None,
+                generated_ranges: None,
                 x_facebook_offsets: None,
                 x_metro_module_paths: None,
                 x_facebook_sources: None,
                 debug_id: Some(DEBUG_ID.parse().expect("valid debug id")).into(),
+                extensions: Default::default(),
             }
         );
     }
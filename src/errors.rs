@@ -4,6 +4,8 @@ use std::io;
 use std::str;
 use std::string;
 
+use crate::validate::Diagnostic;
+
 /// Represents results from this library
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -45,10 +47,22 @@ pub enum Error {
     InvalidRamBundleEntry,
     /// Tried to operate on a non RAM bundle file
     NotARamBundle,
+    /// The magic of a binary sourcemap cache did not match
+    InvalidBinaryCacheMagic,
+    /// The binary sourcemap cache was written by an unsupported version
+    UnsupportedBinaryCacheVersion(u32),
+    /// The binary sourcemap cache was malformed
+    InvalidBinaryCache,
+    /// The sourcemap has more tokens than allowed
+    TooManyTokens(usize),
+    /// The sourcemap failed strict validation
+    ValidationFailed(Vec<Diagnostic>),
     /// Range mapping index is invalid
     InvalidRangeMappingIndex(data_encoding::DecodeError),
 
     InvalidBase64(char),
+    /// A sourcemap references itself, directly or through other sourcemaps
+    CircularReference(String),
 }
 
 impl From<io::Error> for Error {
@@ -124,8 +138,26 @@ impl fmt::Display for Error {
             Error::InvalidRamBundleIndex => write!(f, "invalid module index in ram bundle"),
             Error::InvalidRamBundleEntry => write!(f, "invalid ram bundle module entry"),
             Error::NotARamBundle => write!(f, "not a ram bundle"),
+            Error::InvalidBinaryCacheMagic => write!(f, "invalid magic number for binary cache"),
+            Error::UnsupportedBinaryCacheVersion(version) => {
+                write!(f, "unsupported binary cache version {version}")
+            }
+            Error::InvalidBinaryCache => write!(f, "malformed binary cache"),
+            Error::TooManyTokens(max) => write!(f, "sourcemap has more than {max} tokens"),
+            Error::ValidationFailed(ref diagnostics) => match diagnostics.first() {
+                Some(first) if diagnostics.len() > 1 => write!(
+                    f,
+                    "sourcemap failed validation: {first} (and {} more)",
+                    diagnostics.len() - 1
+                ),
+                Some(first) => write!(f, "sourcemap failed validation: {first}"),
+                None => write!(f, "sourcemap failed validation"),
+            },
             Error::InvalidRangeMappingIndex(err) => write!(f, "invalid range mapping index: {err}"),
             Error::InvalidBase64(c) => write!(f, "invalid base64 character: {c}"),
+            Error::CircularReference(ref url) => {
+                write!(f, "circular sourcemap reference to {url}")
+            }
         }
     }
 }
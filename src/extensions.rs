@@ -0,0 +1,103 @@
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::errors::Result;
+
+/// The extension fields of a sourcemap.
+///
+/// Extensions are all top-level keys of a sourcemap that are not part of
+/// the sourcemap format itself, for instance `x_sentry_meta`.  They are
+/// kept as they are when the sourcemap is encoded, flattened or rewritten.
+///
+/// ```rust
+/// use sourcemap::SourceMap;
+/// let input: &[_] = br#"{
+///     "version": 3,
+///     "sources": [],
+///     "names": [],
+///     "mappings": "",
+///     "x_tool_version": "1.2.3"
+/// }"#;
+/// let mut sm = SourceMap::from_slice(input).unwrap();
+/// assert_eq!(sm.extensions().get("x_tool_version").unwrap(), "1.2.3");
+/// sm.extensions_mut().set("x_build", &42).unwrap();
+/// assert_eq!(sm.extensions().get_as::<u32>("x_build").unwrap(), Some(42));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extensions {
+    pub(crate) fields: BTreeMap<String, Value>,
+}
+
+impl Extensions {
+    /// Returns the value of an extension field.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.get(key)
+    }
+
+    /// Returns the value of an extension field deserialized into `T`.
+    pub fn get_as<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.fields.get(key) {
+            Some(value) => Ok(Some(T::deserialize(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the value of an extension field.
+    ///
+    /// Keys that are part of the sourcemap format are not written out by the
+    /// encoder.
+    pub fn set<K: Into<String>, T: Serialize + ?Sized>(&mut self, key: K, value: &T) -> Result<()> {
+        self.fields.insert(key.into(), serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    /// Removes an extension field and returns its value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.fields.remove(key)
+    }
+
+    /// Returns the number of extension fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if there are no extension fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Iterates over all extension fields ordered by key.
+    pub fn iter(&self) -> ExtensionsIter<'_> {
+        ExtensionsIter(self.fields.iter())
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Extensions {
+    fn from(fields: BTreeMap<String, Value>) -> Extensions {
+        Extensions { fields }
+    }
+}
+
+impl<'a> IntoIterator for &'a Extensions {
+    type Item = (&'a str, &'a Value);
+    type IntoIter = ExtensionsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterates over the fields of [`Extensions`].
+pub struct ExtensionsIter<'a>(btree_map::Iter<'a, String, Value>);
+
+impl<'a> Iterator for ExtensionsIter<'a> {
+    type Item = (&'a str, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key.as_str(), value))
+    }
+}
@@ -1,10 +1,11 @@
-use crate::decoder::{decode, decode_regular, decode_slice};
-use crate::encoder::{encode, Encodable};
+use crate::decoder::{decode, decode_regular, decode_slice, DecodeOptions, DecodeWarning};
+use crate::encoder::{encode, encode_with_options, Encodable, EncodeOptions};
 use crate::errors::{Error, Result};
-use crate::jsontypes::{FacebookScopeMapping, FacebookSources, RawSourceMap};
+use crate::jsontypes::{FacebookScopeMapping, RawSourceMap};
+use crate::scopes::OriginalFrame;
 use crate::types::{DecodedMap, RewriteOptions, SourceMap};
 use crate::utils::greatest_lower_bound;
-use crate::vlq::parse_vlq_segment_into;
+use crate::vlq::{encode_vlq, parse_vlq_segment_into};
 use crate::Token;
 use std::io::{Read, Write};
 use std::ops::{Deref, DerefMut};
@@ -19,12 +20,140 @@ pub struct HermesScopeOffset {
     name_index: u32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl HermesScopeOffset {
+    /// Returns the line where the scope starts (1-indexed).
+    pub fn get_line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns the column where the scope starts (0-indexed).
+    pub fn get_column(&self) -> u32 {
+        self.column
+    }
+
+    /// Returns the index of the scope name in the function map.
+    pub fn get_name_index(&self) -> u32 {
+        self.name_index
+    }
+}
+
+/// The scope information for a single source of a [`SourceMapHermes`].
+///
+/// A function map is a list of locations where scopes start, each of which
+/// refers to the name of the function it belongs to.  Like in Metro, lines
+/// are 1-indexed and columns are 0-indexed.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct HermesFunctionMap {
     names: Vec<String>,
     mappings: Vec<HermesScopeOffset>,
 }
 
+impl HermesFunctionMap {
+    /// Creates a new empty function map.
+    pub fn new() -> HermesFunctionMap {
+        HermesFunctionMap::default()
+    }
+
+    /// Returns the function names referenced by the scopes.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the scopes ordered by their start location.
+    pub fn scopes(&self) -> &[HermesScopeOffset] {
+        &self.mappings
+    }
+
+    /// Looks up the name of a scope by its name index.
+    pub fn get_name(&self, name_index: u32) -> Option<&str> {
+        self.names.get(name_index as usize).map(|n| n.as_str())
+    }
+
+    /// Adds a scope that starts at the given location and returns its index.
+    ///
+    /// `line` is 1-indexed and `column` is 0-indexed.  Scopes are kept
+    /// sorted by their start location.
+    pub fn add_scope(&mut self, line: u32, column: u32, name: &str) -> usize {
+        let name_index = match self.names.iter().position(|n| n == name) {
+            Some(idx) => idx as u32,
+            None => {
+                self.names.push(name.to_owned());
+                self.names.len() as u32 - 1
+            }
+        };
+        let idx = self
+            .mappings
+            .partition_point(|o| (o.line, o.column) <= (line, column));
+        self.mappings.insert(
+            idx,
+            HermesScopeOffset {
+                line,
+                column,
+                name_index,
+            },
+        );
+        idx
+    }
+
+    /// Removes the scope at the given index.
+    pub fn remove_scope(&mut self, idx: usize) -> Option<HermesScopeOffset> {
+        if idx < self.mappings.len() {
+            Some(self.mappings.remove(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Resolves the name of the scope enclosing the given location.
+    ///
+    /// `line` is 1-indexed and `column` is 0-indexed.
+    pub fn lookup_scope(&self, line: u32, column: u32) -> Option<&str> {
+        // Find the closest mapping, just like here:
+        // https://github.com/facebook/metro/blob/63b523eb20e7bdf62018aeaf195bb5a3a1a67f36/packages/metro-symbolicate/src/SourceMetadataMapConsumer.js#L204-L231
+        let (_mapping_idx, mapping) =
+            greatest_lower_bound(&self.mappings, &(line, column), |o| (o.line, o.column))?;
+        self.get_name(mapping.name_index)
+    }
+
+    /// Serializes the scopes the same way Metro does.
+    ///
+    /// Every line starts a new group whose first segment carries the line
+    /// delta, while columns are relative within a line.
+    fn serialize_mappings(&self) -> String {
+        let mut rv = String::new();
+        let mut prev_line = 1;
+        let mut prev_column = 0;
+        let mut prev_name_index = 0;
+
+        for (idx, offset) in self.mappings.iter().enumerate() {
+            let new_line = idx == 0 || offset.line != prev_line;
+            if new_line {
+                if idx > 0 {
+                    rv.push(';');
+                }
+                prev_column = 0;
+            } else {
+                rv.push(',');
+            }
+
+            encode_vlq(&mut rv, i64::from(offset.column) - i64::from(prev_column));
+            encode_vlq(
+                &mut rv,
+                i64::from(offset.name_index) - i64::from(prev_name_index),
+            );
+            if new_line {
+                encode_vlq(&mut rv, i64::from(offset.line) - i64::from(prev_line));
+            }
+
+            prev_line = offset.line;
+            prev_column = offset.column;
+            prev_name_index = offset.name_index;
+        }
+
+        rv
+    }
+}
+
 /// Represents a `react-native`-style SourceMap, which has additional scope
 /// information embedded.
 #[derive(Debug, Clone, PartialEq)]
@@ -32,9 +161,9 @@ pub struct SourceMapHermes {
     pub(crate) sm: SourceMap,
     // There should be one `HermesFunctionMap` per each `sources` entry in the main SourceMap.
     function_maps: Vec<Option<HermesFunctionMap>>,
-    // XXX: right now, I am too lazy to actually serialize the above `function_maps`
-    // back into json types, so just keep the original json. Might be a bit inefficient, but meh.
-    raw_facebook_sources: FacebookSources,
+    // The metadata entries as they were decoded.  Entries after the function
+    // map and function maps that could not be parsed are written back as is.
+    raw_facebook_sources: Vec<Option<Vec<FacebookScopeMapping>>>,
 }
 
 impl Deref for SourceMapHermes {
@@ -53,10 +182,31 @@ impl DerefMut for SourceMapHermes {
 
 impl Encodable for SourceMapHermes {
     fn as_raw_sourcemap(&self) -> RawSourceMap {
-        // TODO: need to serialize the `HermesFunctionMap` mappings
         let mut rsm = self.sm.as_raw_sourcemap();
-        rsm.x_facebook_sources
-            .clone_from(&self.raw_facebook_sources);
+        let source_count = self
+            .function_maps
+            .len()
+            .max(self.raw_facebook_sources.len());
+        rsm.x_facebook_sources = Some(
+            (0..source_count)
+                .map(|idx| {
+                    let raw = self.raw_facebook_sources.get(idx).and_then(Option::as_ref);
+                    match self.function_maps.get(idx).and_then(Option::as_ref) {
+                        Some(function_map) => {
+                            let mut metadata = vec![FacebookScopeMapping {
+                                names: function_map.names.clone(),
+                                mappings: function_map.serialize_mappings(),
+                            }];
+                            metadata.extend(
+                                raw.into_iter().flat_map(|raw| raw.iter().skip(1)).cloned(),
+                            );
+                            Some(metadata)
+                        }
+                        None => raw.cloned(),
+                    }
+                })
+                .collect(),
+        );
         rsm
     }
 }
@@ -91,6 +241,13 @@ impl SourceMapHermes {
         encode(self, w)
     }
 
+    /// Writes a sourcemap into a writer with the given options.
+    ///
+    /// See [`SourceMap::to_writer_with_options`](struct.SourceMap.html#method.to_writer_with_options)
+    pub fn to_writer_with_options<W: Write>(&self, w: W, options: &EncodeOptions) -> Result<()> {
+        encode_with_options(self, w, options)
+    }
+
     /// Given a bytecode offset, this will find the enclosing scopes function
     /// name.
     pub fn get_original_function_name(&self, bytecode_offset: u32) -> Option<&str> {
@@ -101,24 +258,80 @@ impl SourceMapHermes {
 
     /// Resolves the name of the enclosing function for the given [`Token`].
     pub fn get_scope_for_token(&self, token: Token) -> Option<&str> {
-        let function_map = self
-            .function_maps
-            .get(token.get_src_id() as usize)?
-            .as_ref()?;
-
-        // Find the closest mapping, just like here:
-        // https://github.com/facebook/metro/blob/63b523eb20e7bdf62018aeaf195bb5a3a1a67f36/packages/metro-symbolicate/src/SourceMetadataMapConsumer.js#L204-L231
         // Mappings use 1-based index for lines, and 0-based index for cols, as seen here:
         // https://github.com/facebook/metro/blob/f2d80cebe66d3c64742f67259f41da26e83a0d8d/packages/metro/src/Server/symbolicate.js#L58-L60
-        let (_mapping_idx, mapping) = greatest_lower_bound(
-            &function_map.mappings,
-            &(token.get_src_line() + 1, token.get_src_col()),
-            |o| (o.line, o.column),
-        )?;
-        function_map
-            .names
-            .get(mapping.name_index as usize)
-            .map(|n| n.as_str())
+        self.get_function_map(token.get_src_id())?
+            .lookup_scope(token.get_src_line() + 1, token.get_src_col())
+    }
+
+    /// Returns the function map for a source.
+    pub fn get_function_map(&self, src_id: u32) -> Option<&HermesFunctionMap> {
+        self.function_maps.get(src_id as usize)?.as_ref()
+    }
+
+    /// Returns the function map for a source as a mutable ref.
+    pub fn get_function_map_mut(&mut self, src_id: u32) -> Option<&mut HermesFunctionMap> {
+        self.function_maps.get_mut(src_id as usize)?.as_mut()
+    }
+
+    /// Sets the function map for a source.
+    ///
+    /// Setting the function map to `None` also drops any other metadata of
+    /// the source.  This panics if a function map is set for a source that
+    /// does not exist.
+    pub fn set_function_map(&mut self, src_id: u32, function_map: Option<HermesFunctionMap>) {
+        assert!(
+            src_id < self.sm.get_source_count(),
+            "Cannot set function map for non existing source"
+        );
+        if self.function_maps.len() != self.sm.get_source_count() as usize {
+            self.function_maps
+                .resize(self.sm.get_source_count() as usize, None);
+        }
+        if function_map.is_none() {
+            if let Some(raw) = self.raw_facebook_sources.get_mut(src_id as usize) {
+                *raw = None;
+            }
+        }
+        self.function_maps[src_id as usize] = function_map;
+    }
+
+    /// Reorders the function maps after the sources have been reordered.
+    ///
+    /// `mapping` contains the previous source index for every new source
+    /// index.  Function maps of sources that do not show up in `mapping` are
+    /// dropped.
+    pub fn remap_function_maps(&mut self, mapping: &[u32]) {
+        let mut function_maps = std::mem::take(&mut self.function_maps);
+        self.function_maps = mapping
+            .iter()
+            .map(|&idx| function_maps.get_mut(idx as usize).and_then(Option::take))
+            .collect();
+        let mut raw_facebook_sources = std::mem::take(&mut self.raw_facebook_sources);
+        self.raw_facebook_sources = mapping
+            .iter()
+            .map(|&idx| {
+                raw_facebook_sources
+                    .get_mut(idx as usize)
+                    .and_then(Option::take)
+            })
+            .collect();
+    }
+
+    /// Looks up the original frames for a given line and column.
+    ///
+    /// Frames are named after the enclosing scope from the function maps
+    /// unless the sourcemap carries other scope information.
+    ///
+    /// See [`SourceMap::lookup_frames`](struct.SourceMap.html#method.lookup_frames)
+    pub fn lookup_frames(&self, line: u32, col: u32) -> Vec<OriginalFrame<'_>> {
+        let mut frames = self.sm.lookup_frames(line, col);
+        if let (Some(frame), Some(token)) = (frames.last_mut(), self.sm.lookup_token(line, col)) {
+            if frame.name.is_none() {
+                frame.name = self.get_scope_for_token(token);
+            }
+        }
+        frames
     }
 
     /// This rewrites the sourcemap according to the provided rewrite
@@ -128,34 +341,27 @@ impl SourceMapHermes {
     pub fn rewrite(self, options: &RewriteOptions<'_>) -> Result<Self> {
         let Self {
             sm,
-            mut function_maps,
-            mut raw_facebook_sources,
+            function_maps,
+            raw_facebook_sources,
         } = self;
 
         let (sm, mapping) = sm.rewrite_with_mapping(options)?;
 
-        if function_maps.len() >= mapping.len() {
-            function_maps = mapping
-                .iter()
-                .map(|idx| function_maps[*idx as usize].take())
-                .collect();
-            raw_facebook_sources = raw_facebook_sources.map(|mut sources| {
-                mapping
-                    .into_iter()
-                    .map(|idx| sources[idx as usize].take())
-                    .collect()
-            });
-        }
-
-        Ok(Self {
+        let mut rv = Self {
             sm,
             function_maps,
             raw_facebook_sources,
-        })
+        };
+        rv.remap_function_maps(&mapping);
+        Ok(rv)
     }
 }
 
-pub fn decode_hermes(mut rsm: RawSourceMap) -> Result<SourceMapHermes> {
+pub fn decode_hermes(
+    mut rsm: RawSourceMap,
+    options: &DecodeOptions,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<SourceMapHermes> {
     let x_facebook_sources = rsm
         .x_facebook_sources
         .take()
@@ -211,10 +417,10 @@ pub fn decode_hermes(mut rsm: RawSourceMap) -> Result<SourceMapHermes> {
         })
         .collect();
 
-    let sm = decode_regular(rsm)?;
+    let sm = decode_regular(rsm, options, warnings)?;
     Ok(SourceMapHermes {
         sm,
         function_maps,
-        raw_facebook_sources: Some(x_facebook_sources),
+        raw_facebook_sources: x_facebook_sources,
     })
 }
@@ -0,0 +1,186 @@
+use crate::builder::SourceMapBuilder;
+use crate::types::SourceMap;
+
+/// The rules used by [`IgnoreRules::default_rules`].
+const DEFAULT_GLOBS: &[&str] = &[
+    "node_modules/",
+    "bower_components/",
+    "webpack/bootstrap*",
+    "webpack/runtime/",
+    "(webpack)/buildin/",
+    "(webpack)/hot/",
+];
+
+/// Matches a glob pattern against a string.
+///
+/// `*` matches any run of characters except `/`, `**` matches any run of
+/// characters and `?` matches a single character except `/`.
+fn glob_match(pattern: &[u8], s: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => s.is_empty(),
+        Some((b'*', rest)) if rest.first() == Some(&b'*') => {
+            let rest = &rest[1..];
+            (0..=s.len()).any(|idx| glob_match(rest, &s[idx..]))
+        }
+        Some((b'*', rest)) => {
+            let segment_end = s.iter().position(|&c| c == b'/').unwrap_or(s.len());
+            (0..=segment_end).any(|idx| glob_match(rest, &s[idx..]))
+        }
+        Some((b'?', rest)) => match s.split_first() {
+            Some((&c, s_rest)) if c != b'/' => glob_match(rest, s_rest),
+            _ => false,
+        },
+        Some((&p, rest)) => match s.split_first() {
+            Some((&c, s_rest)) if c == p => glob_match(rest, s_rest),
+            _ => false,
+        },
+    }
+}
+
+/// A single rule of [`IgnoreRules`].
+#[derive(Debug, Clone)]
+pub enum IgnoreRule {
+    /// A glob pattern that is matched against whole path segments of a
+    /// source.
+    ///
+    /// `node_modules/` matches `webpack:///./node_modules/react/index.js`
+    /// but not `src/my_node_modules/index.js`.  A pattern that ends with a
+    /// `/` only matches directories.
+    Glob(String),
+    /// A regular expression that is searched for in a source.
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl IgnoreRule {
+    /// Checks if the rule matches the given source.
+    pub fn is_match(&self, source: &str) -> bool {
+        match *self {
+            IgnoreRule::Glob(ref pattern) => {
+                let (pattern, dir_only) = match pattern.strip_suffix('/') {
+                    Some(pattern) => (pattern.as_bytes(), true),
+                    None => (pattern.as_bytes(), false),
+                };
+                let source = source.as_bytes();
+                let slashes = (0..source.len()).filter(|&idx| source[idx] == b'/');
+                let starts: Vec<usize> = std::iter::once(0)
+                    .chain(slashes.clone().map(|idx| idx + 1))
+                    .collect();
+                let ends: Vec<usize> = if dir_only {
+                    slashes.collect()
+                } else {
+                    slashes.chain(Some(source.len())).collect()
+                };
+                starts.into_iter().any(|start| {
+                    ends.iter()
+                        .filter(|&&end| end >= start)
+                        .any(|&end| glob_match(pattern, &source[start..end]))
+                })
+            }
+            #[cfg(feature = "regex")]
+            IgnoreRule::Regex(ref regex) => regex.is_match(source),
+        }
+    }
+}
+
+/// A set of rules that decides which sources belong on the ignore list.
+///
+/// Most bundlers do not emit an `ignoreList`, which leaves debuggers and
+/// error reporting unable to tell application code from third-party and
+/// bundler runtime code.  The rules can be applied to a sourcemap directly,
+/// to a [`SourceMapBuilder`] or as part of
+/// [`SourceMap::rewrite`](crate::SourceMap::rewrite) via
+/// [`RewriteOptions::ignore_rules`](crate::RewriteOptions::ignore_rules).
+///
+/// ```rust
+/// use sourcemap::{IgnoreRules, SourceMap};
+/// let input: &[_] = br#"{
+///     "version": 3,
+///     "sources": ["webpack:///webpack/bootstrap", "webpack:///./src/app.js",
+///                 "webpack:///./node_modules/react/index.js"],
+///     "names": [],
+///     "mappings": "AAAA,CCAA,CCAA"
+/// }"#;
+/// let mut sm = SourceMap::from_slice(input).unwrap();
+/// IgnoreRules::default_rules().apply(&mut sm);
+/// assert_eq!(sm.ignore_list().collect::<Vec<_>>(), vec![&0, &2]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Creates an empty set of rules.
+    pub fn new() -> IgnoreRules {
+        IgnoreRules::default()
+    }
+
+    /// Creates rules for common dependency directories and bundler
+    /// runtimes.
+    ///
+    /// This covers `node_modules/`, `bower_components/` and the webpack
+    /// bootstrap, runtime, buildin and hot reloading modules.
+    pub fn default_rules() -> IgnoreRules {
+        let mut rules = IgnoreRules::new();
+        for glob in DEFAULT_GLOBS {
+            rules.add_glob(glob);
+        }
+        rules
+    }
+
+    /// Adds a rule.
+    pub fn add_rule(&mut self, rule: IgnoreRule) {
+        self.rules.push(rule);
+    }
+
+    /// Adds a glob rule.  See [`IgnoreRule::Glob`].
+    pub fn add_glob(&mut self, pattern: &str) {
+        self.add_rule(IgnoreRule::Glob(pattern.to_string()));
+    }
+
+    /// Adds a regular expression rule.
+    #[cfg(feature = "regex")]
+    pub fn add_regex(&mut self, regex: regex::Regex) {
+        self.add_rule(IgnoreRule::Regex(regex));
+    }
+
+    /// Returns the rules.
+    pub fn rules(&self) -> &[IgnoreRule] {
+        &self.rules
+    }
+
+    /// Checks if any rule matches the given source.
+    pub fn is_ignored(&self, source: &str) -> bool {
+        self.rules.iter().any(|rule| rule.is_match(source))
+    }
+
+    /// Adds all sources of the sourcemap that match a rule to its ignore
+    /// list.
+    ///
+    /// Sources are matched as returned by
+    /// [`SourceMap::get_source`](crate::SourceMap::get_source), that is with
+    /// the `sourceRoot` applied.  Sources that are already on the ignore
+    /// list stay on it.
+    pub fn apply(&self, sm: &mut SourceMap) {
+        for src_id in 0..sm.get_source_count() {
+            if sm
+                .get_source(src_id)
+                .is_some_and(|src| self.is_ignored(src))
+            {
+                sm.add_to_ignore_list(src_id);
+            }
+        }
+    }
+
+    /// Adds all sources of the builder that match a rule to its ignore list.
+    pub fn apply_to_builder(&self, builder: &mut SourceMapBuilder) {
+        let mut src_id = 0;
+        while let Some(src) = builder.get_source(src_id) {
+            if self.is_ignored(src) {
+                builder.add_to_ignore_list(src_id);
+            }
+            src_id += 1;
+        }
+    }
+}
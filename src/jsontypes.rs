@@ -1,7 +1,10 @@
 use debugid::DebugId;
 use serde::de::IgnoredAny;
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::value::RawValue;
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -49,6 +52,15 @@ pub struct RawSourceMap {
     pub mappings: Option<String>,
     #[serde(rename = "ignoreList", skip_serializing_if = "Option::is_none")]
     pub ignore_list: Option<Vec<u32>>,
+    #[serde(
+        rename = "x_google_ignoreList",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub x_google_ignore_list: Option<Vec<u32>>,
+    #[serde(rename = "originalScopes", skip_serializing_if = "Option::is_none")]
+    pub original_scopes: Option<Vec<Option<String>>>,
+    #[serde(rename = "generatedRanges", skip_serializing_if = "Option::is_none")]
+    pub generated_ranges: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub x_facebook_offsets: Option<Vec<Option<u32>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -57,6 +69,49 @@ pub struct RawSourceMap {
     pub x_facebook_sources: FacebookSources,
     #[serde(flatten)]
     pub debug_id: DebugIdField,
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+/// Keys that are always written from the fields of `RawSourceMap`.
+///
+/// `x_facebook_offsets` and `x_metro_module_paths` are only used by index
+/// maps and kept as extensions on regular sourcemaps.
+const RESERVED_KEYS: &[&str] = &[
+    "version",
+    "file",
+    "sources",
+    "sourceRoot",
+    "sourcesContent",
+    "sections",
+    "names",
+    "rangeMappings",
+    "mappings",
+    "ignoreList",
+    "x_google_ignoreList",
+    "originalScopes",
+    "generatedRanges",
+    "x_facebook_sources",
+    "debugId",
+    "debug_id",
+];
+
+impl RawSourceMap {
+    /// Adds the extensions that do not collide with fields of this
+    /// sourcemap.
+    pub fn set_extensions(&mut self, extensions: &BTreeMap<String, Value>) {
+        let has_offsets = self.x_facebook_offsets.is_some();
+        let has_module_paths = self.x_metro_module_paths.is_some();
+        self.extensions = extensions
+            .iter()
+            .filter(|(key, _)| match key.as_str() {
+                "x_facebook_offsets" => !has_offsets,
+                "x_metro_module_paths" => !has_module_paths,
+                key => !RESERVED_KEYS.contains(&key),
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+    }
 }
 
 #[derive(Deserialize)]
@@ -73,6 +128,97 @@ pub struct MinimalRawSourceMap {
     pub mappings: Option<IgnoredAny>,
 }
 
+/// A variant of `RawSourceMap` that borrows all large values from the
+/// input without parsing them.
+///
+/// This is used by the borrowed and streaming sourcemaps as well as by
+/// `decode_with_options`, which only parses the values it needs.
+#[derive(Deserialize)]
+pub(crate) struct BorrowedRawSourceMap<'a> {
+    pub version: Option<u32>,
+    pub file: Option<Value>,
+    #[serde(borrow)]
+    pub sources: Option<Vec<&'a RawValue>>,
+    #[serde(rename = "sourceRoot")]
+    pub source_root: Option<String>,
+    #[serde(rename = "sourcesContent", borrow)]
+    pub sources_content: Option<Vec<&'a RawValue>>,
+    #[serde(borrow)]
+    pub sections: Option<&'a RawValue>,
+    #[serde(borrow)]
+    pub names: Option<Vec<&'a RawValue>>,
+    #[serde(rename = "rangeMappings", borrow)]
+    pub range_mappings: Option<&'a RawValue>,
+    #[serde(borrow)]
+    pub mappings: Option<&'a RawValue>,
+    #[serde(rename = "ignoreList")]
+    pub ignore_list: Option<Vec<u32>>,
+    #[serde(rename = "x_google_ignoreList")]
+    pub x_google_ignore_list: Option<Vec<u32>>,
+    #[serde(rename = "originalScopes", borrow)]
+    pub original_scopes: Option<&'a RawValue>,
+    #[serde(rename = "generatedRanges", borrow)]
+    pub generated_ranges: Option<&'a RawValue>,
+    #[serde(borrow)]
+    pub x_facebook_offsets: Option<&'a RawValue>,
+    #[serde(borrow)]
+    pub x_metro_module_paths: Option<&'a RawValue>,
+    #[serde(borrow)]
+    pub x_facebook_sources: Option<&'a RawValue>,
+    #[serde(rename = "debugId")]
+    pub debug_id: Option<DebugId>,
+    #[serde(rename = "debug_id")]
+    pub legacy_debug_id: Option<DebugId>,
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BorrowedRawSection<'a> {
+    pub offset: RawSectionOffset,
+    pub url: Option<String>,
+    #[serde(borrow)]
+    pub map: Option<&'a RawValue>,
+}
+
+/// Parses a raw JSON string (or `null`) without copying it unless it
+/// contains escape sequences.
+pub(crate) fn parse_json_str(raw: &RawValue) -> crate::errors::Result<Option<Cow<'_, str>>> {
+    let raw = raw.get();
+    if raw == "null" {
+        return Ok(None);
+    }
+    if let Some(inner) = raw.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        if !inner.contains('\\') {
+            return Ok(Some(Cow::Borrowed(inner)));
+        }
+    }
+    Ok(serde_json::from_str::<Option<String>>(raw)?.map(Cow::Owned))
+}
+
+/// Parses the `file` of a sourcemap, which sometimes is not a string for
+/// unexplicable reasons.
+pub(crate) fn parse_json_file(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        _ => "<invalid>".into(),
+    }
+}
+
+/// Parses an entry of `names`.
+///
+/// apparently we can encounter some non string types in real world
+/// sourcemaps :(
+pub(crate) fn parse_json_name(raw: &RawValue) -> Cow<'_, str> {
+    match parse_json_str(raw) {
+        Ok(name) => name.unwrap_or_default(),
+        Err(_) => match serde_json::from_str(raw.get()) {
+            Ok(Value::Number(num)) => num.to_string().into(),
+            _ => "".into(),
+        },
+    }
+}
+
 /// This struct represents a `RawSourceMap`'s debug ID fields.
 ///
 /// The reason this exists as a seperate struct is so that we can have custom deserialization
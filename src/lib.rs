@@ -21,7 +21,8 @@
 //! This crate can load JavaScript sourcemaps from JSON files.  It uses
 //! `serde` for parsing of the JSON data.  Due to the nature of sourcemaps
 //! the entirety of the file must be loaded into memory which can be quite
-//! memory intensive.
+//! memory intensive.  For very large sourcemaps the `StreamingSourceMap`
+//! decodes mappings on demand and loads source contents lazily.
 //!
 //! Usage:
 //!
@@ -44,34 +45,63 @@
 //! current list of feature flags:
 //!
 //! * `ram_bundle`: turns on RAM bundle support
+//! * `regex`: turns on regular expression rules for [`IgnoreRules`]
 //!
-pub use crate::builder::SourceMapBuilder;
-pub use crate::decoder::{decode, decode_data_url, decode_slice};
+pub use crate::binary::BinarySourceMap;
+pub use crate::borrowed::{BorrowedSourceMap, BorrowedToken};
+pub use crate::builder::{SourceMapBuilder, SourceMapIndexBuilder};
+pub use crate::decoder::{
+    decode, decode_data_url, decode_slice, decode_strict, decode_with_options, DecodeOptions,
+    DecodeWarning, MappingsIter,
+};
 pub use crate::detector::{
     is_sourcemap, is_sourcemap_slice, locate_sourcemap_reference, locate_sourcemap_reference_slice,
     SourceMapRef,
 };
+pub use crate::diff::{diff, MappingChange, MappingDiff, OriginalPosition, SourceMapDiff};
+pub use crate::encoder::{EncodeOptions, IgnoreListKey};
 pub use crate::errors::{Error, Result};
-pub use crate::hermes::SourceMapHermes;
+pub use crate::extensions::{Extensions, ExtensionsIter};
+pub use crate::hermes::{HermesFunctionMap, HermesScopeOffset, SourceMapHermes};
+pub use crate::ignore::{IgnoreRule, IgnoreRules};
+pub use crate::resolver::{FsResolver, Resolver};
+pub use crate::scopes::{Callsite, GeneratedRange, OriginalFrame, OriginalScope, OriginalScopeRef};
 pub use crate::sourceview::SourceView;
+pub use crate::stats::{SourceMapStats, UnmappedRun};
+pub use crate::streaming::StreamingSourceMap;
 pub use crate::types::{
-    DecodedMap, NameIter, RawToken, RewriteOptions, SourceContentsIter, SourceIter, SourceMap,
-    SourceMapIndex, SourceMapSection, SourceMapSectionIter, Token, TokenIter,
+    Bias, DecodedMap, GeneratedSpan, GeneratedSpanIter, LookupOptions, NameIter, OriginalSpans,
+    RawToken, RewriteOptions, SourceContentsIter, SourceIter, SourceMap, SourceMapIndex,
+    SourceMapSection, SourceMapSectionIter, Token, TokenIter, TokenStore,
 };
 pub use crate::utils::make_relative_path;
+pub use crate::validate::{Diagnostic, DiagnosticKind};
 
+mod binary;
+mod borrowed;
 mod builder;
 mod decoder;
 mod detector;
+mod diff;
 mod encoder;
 mod errors;
+mod extensions;
 mod hermes;
+mod ignore;
 mod js_identifiers;
 mod jsontypes;
+mod resolver;
+mod scopes;
 mod sourceview;
+mod stats;
+mod streaming;
 mod types;
 mod utils;
+mod validate;
 
+pub mod analysis;
+pub mod coverage;
 #[cfg(feature = "ram_bundle")]
 pub mod ram_bundle;
+pub mod stacktrace;
 pub mod vlq;
@@ -0,0 +1,285 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use url::Url;
+
+use crate::decoder::{decode_data_url, decode_slice};
+use crate::detector::{locate_sourcemap_reference_slice, SourceMapRef};
+use crate::errors::{Error, Result};
+use crate::sourceview::SourceView;
+use crate::stacktrace::SourceMapLoader;
+use crate::types::{DecodedMap, SourceMap};
+
+thread_local! {
+    /// The URLs of the sourcemaps currently being fetched on this thread.
+    static RESOLVING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks a URL as being fetched until dropped.
+struct ResolvingGuard;
+
+impl ResolvingGuard {
+    fn enter(url: &str) -> Result<ResolvingGuard> {
+        RESOLVING.with(|resolving| {
+            let mut resolving = resolving.borrow_mut();
+            if resolving.iter().any(|x| x == url) {
+                return Err(Error::CircularReference(url.to_string()));
+            }
+            resolving.push(url.to_string());
+            Ok(ResolvingGuard)
+        })
+    }
+}
+
+impl Drop for ResolvingGuard {
+    fn drop(&mut self) {
+        RESOLVING.with(|resolving| resolving.borrow_mut().pop());
+    }
+}
+
+/// Joins a reference with the URL or path of the file that contains it.
+///
+/// Data URLs and absolute URLs are returned unchanged.  If `base` is not a
+/// URL it is treated as a file path.
+fn join_url(base: &str, reference: &str) -> String {
+    if reference.starts_with("data:") || Url::parse(reference).is_ok() {
+        return reference.to_string();
+    }
+    if Url::parse(base).is_ok() {
+        let sm_ref = SourceMapRef::Ref(reference.to_string());
+        if let Some(url) = sm_ref.resolve(base) {
+            return url;
+        }
+    }
+    match Path::new(base).parent() {
+        Some(parent) => parent.join(reference).to_string_lossy().into_owned(),
+        None => reference.to_string(),
+    }
+}
+
+/// Fetches and decodes the sourcemap at `url` without consulting any cache.
+///
+/// Sections of index maps that only carry a URL are loaded through the
+/// resolver, relative to `url`.  Sections that refer back to a sourcemap
+/// that is still being loaded fail with `CircularReference`.
+fn fetch_sourcemap<R: Resolver + ?Sized>(
+    resolver: &mut R,
+    url: &str,
+) -> Result<Option<DecodedMap>> {
+    let _guard = ResolvingGuard::enter(url)?;
+    let mut sm = if url.starts_with("data:") {
+        decode_data_url(url)?
+    } else {
+        match resolver.fetch(url)? {
+            Some(data) => decode_slice(&data)?,
+            None => return Ok(None),
+        }
+    };
+
+    if let DecodedMap::Index(ref mut smi) = sm {
+        smi.resolve_sections(|section_url| {
+            Ok(resolver
+                .load_sourcemap(&join_url(url, section_url))?
+                .map(|map| Arc::try_unwrap(map).unwrap_or_else(|map| (*map).clone())))
+        })?;
+    }
+
+    Ok(Some(sm))
+}
+
+/// Loads minified files, sourcemaps and sources.
+///
+/// Implementors only need to provide [`fetch`](Resolver::fetch).  The
+/// provided methods take care of following `sourceMappingURL` references,
+/// decoding embedded data URLs, resolving relative URLs and loading the
+/// sections of index maps that are only referenced by URL.
+///
+/// URLs passed to the resolver are either absolute URLs or, if the file
+/// that referenced them was given as a path, file paths.
+pub trait Resolver {
+    /// Reads the file or URL.
+    ///
+    /// Returns `None` if it does not exist.
+    fn fetch(&mut self, url: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Loads the sourcemap at the given URL.
+    ///
+    /// Implementations are free to cache the returned maps.
+    fn load_sourcemap(&mut self, url: &str) -> Result<Option<Arc<DecodedMap>>> {
+        Ok(fetch_sourcemap(self, url)?.map(Arc::new))
+    }
+
+    /// Loads the sourcemap referenced by a minified file.
+    ///
+    /// This looks for a `sourceMappingURL` comment in the minified file and
+    /// resolves it relative to `minified_url`.
+    fn resolve_sourcemap(&mut self, minified_url: &str) -> Result<Option<Arc<DecodedMap>>> {
+        let sm_ref = match self.fetch(minified_url)? {
+            Some(source) => locate_sourcemap_reference_slice(&source)?,
+            None => return Ok(None),
+        };
+        match sm_ref {
+            Some(sm_ref) => self.load_sourcemap(&join_url(minified_url, sm_ref.get_url())),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the contents of all sources of a sourcemap that does not embed
+    /// them.
+    ///
+    /// Source names are resolved relative to `sourcemap_url` after the
+    /// `sourceRoot` is applied.  Sources that cannot be found are left
+    /// empty.
+    fn load_source_contents(&mut self, sourcemap_url: &str, sm: &mut SourceMap) -> Result<()> {
+        for idx in 0..sm.get_source_count() {
+            if sm.get_source_contents(idx).is_some() {
+                continue;
+            }
+            let source_url = match sm.get_source(idx) {
+                Some(source) => join_url(sourcemap_url, source),
+                None => continue,
+            };
+            if let Some(data) = self.fetch(&source_url)? {
+                sm.set_source_contents(idx, Some(std::str::from_utf8(&data)?));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every resolver can load sourcemaps for a [`Symbolicator`].
+///
+/// Script URLs are resolved with
+/// [`resolve_sourcemap`](Resolver::resolve_sourcemap).  Scripts or
+/// sourcemaps that cannot be loaded are treated as missing.
+///
+/// [`Symbolicator`]: crate::stacktrace::Symbolicator
+impl<R: Resolver> SourceMapLoader for R {
+    fn load_for_url(&mut self, url: &str) -> Option<DecodedMap> {
+        let sm = self.resolve_sourcemap(url).ok()??;
+        Some(Arc::try_unwrap(sm).unwrap_or_else(|sm| (*sm).clone()))
+    }
+
+    fn load_minified_source(&mut self, url: &str) -> Option<SourceView> {
+        let data = self.fetch(url).ok()??;
+        Some(SourceView::from_string(String::from_utf8(data).ok()?))
+    }
+}
+
+/// A small least recently used cache.
+#[derive(Debug)]
+struct LruCache<V> {
+    capacity: usize,
+    // the most recently used entry is last
+    entries: Vec<(String, V)>,
+}
+
+impl<V: Clone> LruCache<V> {
+    fn get(&mut self, key: &str) -> Option<V> {
+        let idx = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(idx);
+        let rv = entry.1.clone();
+        self.entries.push(entry);
+        Some(rv)
+    }
+
+    fn insert(&mut self, key: String, value: V) {
+        self.entries.retain(|(k, _)| *k != key);
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, value));
+    }
+}
+
+/// A [`Resolver`] that reads from the local filesystem.
+///
+/// Both plain paths and `file://` URLs are supported.  Decoded sourcemaps
+/// are kept in a least recently used cache so that sourcemaps shared by
+/// several files or sections are only loaded once.
+///
+/// ```no_run
+/// use sourcemap::{FsResolver, Resolver};
+/// let mut resolver = FsResolver::new();
+/// let sm = resolver.resolve_sourcemap("dist/bundle.min.js").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FsResolver {
+    cache: LruCache<Arc<DecodedMap>>,
+}
+
+impl Default for FsResolver {
+    fn default() -> FsResolver {
+        FsResolver::new()
+    }
+}
+
+impl FsResolver {
+    /// Creates a new resolver that caches up to 32 sourcemaps.
+    pub fn new() -> FsResolver {
+        FsResolver::with_capacity(32)
+    }
+
+    /// Creates a new resolver that caches up to `capacity` sourcemaps.
+    pub fn with_capacity(capacity: usize) -> FsResolver {
+        FsResolver {
+            cache: LruCache {
+                capacity,
+                entries: vec![],
+            },
+        }
+    }
+
+    /// Returns the maximum number of cached sourcemaps.
+    pub fn get_capacity(&self) -> usize {
+        self.cache.capacity
+    }
+
+    /// Returns the number of currently cached sourcemaps.
+    pub fn get_cached_count(&self) -> usize {
+        self.cache.entries.len()
+    }
+
+    /// Removes all sourcemaps from the cache.
+    pub fn clear_cache(&mut self) {
+        self.cache.entries.clear();
+    }
+}
+
+impl Resolver for FsResolver {
+    fn fetch(&mut self, url: &str) -> Result<Option<Vec<u8>>> {
+        let path = match Url::parse(url) {
+            Ok(url) if url.scheme() == "file" => match url.to_file_path() {
+                Ok(path) => path,
+                Err(()) => return Ok(None),
+            },
+            // other schemes cannot be loaded from the filesystem.  Single
+            // letter schemes are windows drive letters.
+            Ok(url) if url.scheme().len() > 1 => return Ok(None),
+            _ => url.into(),
+        };
+        match fs::read(path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn load_sourcemap(&mut self, url: &str) -> Result<Option<Arc<DecodedMap>>> {
+        if let Some(sm) = self.cache.get(url) {
+            return Ok(Some(sm));
+        }
+        let sm = fetch_sourcemap(self, url)?.map(Arc::new);
+        // data URLs are cheap to decode and would only bloat the cache
+        if let Some(sm) = sm.as_ref().filter(|_| !url.starts_with("data:")) {
+            self.cache.insert(url.to_string(), Arc::clone(sm));
+        }
+        Ok(sm)
+    }
+}
@@ -0,0 +1,751 @@
+//! Implements the scopes proposal for source maps.
+//!
+//! See <https://github.com/tc39/source-map/blob/main/proposals/scopes.md>
+//!
+//! Scope information is carried in two fields.  `originalScopes` holds one
+//! string per entry in `sources`, each encoding a single tree of scopes in
+//! the original source.  `generatedRanges` encodes a tree of ranges in the
+//! generated file, each of which may point back at the original scope it was
+//! produced from and at the callsite it was inlined into.
+//!
+//! Both fields are lists of comma separated VLQ items.  An original scope
+//! item is either a start (`line, column, flags, [kind], [name],
+//! variables*`) or an end (`line, column`).  Lines are relative to the
+//! previous item, columns are absolute and name references are relative to
+//! the previous name reference in the same source.  The flags are `0x1` (has
+//! name), `0x2` (has kind) and `0x4` (is stack frame).
+//!
+//! A generated range item is either a start (`column, flags, [definition],
+//! [callsite], bindings*`) or an end (`column`).  Lines are separated by `;`
+//! and columns are relative to the previous item on the same line.  The
+//! flags are `0x1` (has definition), `0x2` (has callsite), `0x4` (is stack
+//! frame) and `0x8` (is hidden).  A definition is a source index and the
+//! index of the scope in a pre-order walk of that source's scope tree.  A
+//! callsite is a source index, line and column.  Every value of a definition
+//! or callsite is relative to the previous one as long as the preceding
+//! values did not change, otherwise it is absolute.  Bindings are absolute
+//! indexes into `names` where `-1` marks an unavailable binding.
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use crate::types::SourceMap;
+use crate::vlq::{encode_vlq, parse_vlq_segment_into};
+
+const ORIGINAL_SCOPE_HAS_NAME: i64 = 0x1;
+const ORIGINAL_SCOPE_HAS_KIND: i64 = 0x2;
+const ORIGINAL_SCOPE_IS_STACK_FRAME: i64 = 0x4;
+
+const GENERATED_RANGE_HAS_DEFINITION: i64 = 0x1;
+const GENERATED_RANGE_HAS_CALLSITE: i64 = 0x2;
+const GENERATED_RANGE_IS_STACK_FRAME: i64 = 0x4;
+const GENERATED_RANGE_IS_HIDDEN: i64 = 0x8;
+
+/// Represents a scope in an original source.
+///
+/// Positions are 0-indexed line and column pairs in the original source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalScope {
+    /// the position where the scope starts
+    pub start: (u32, u32),
+    /// the position where the scope ends
+    pub end: (u32, u32),
+    /// the kind of the scope, for instance `function` or `block`
+    pub kind: Option<Arc<str>>,
+    /// the name of the scope, usually the function name
+    pub name: Option<Arc<str>>,
+    /// true if this scope shows up as a frame in stack traces
+    pub is_stack_frame: bool,
+    /// the names of the variables declared in this scope
+    pub variables: Vec<Arc<str>>,
+    /// the scopes nested in this scope
+    pub children: Vec<OriginalScope>,
+}
+
+impl OriginalScope {
+    /// Creates a new original scope without any metadata or children.
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> OriginalScope {
+        OriginalScope {
+            start,
+            end,
+            kind: None,
+            name: None,
+            is_stack_frame: false,
+            variables: vec![],
+            children: vec![],
+        }
+    }
+
+    /// Returns the name of the scope if it has one.
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the kind of the scope if it has one.
+    pub fn get_kind(&self) -> Option<&str> {
+        self.kind.as_deref()
+    }
+
+    /// Looks up a scope by its index in a pre-order walk of this tree.
+    ///
+    /// The scope itself has index 0.
+    pub fn get_scope_by_index(&self, idx: u32) -> Option<&OriginalScope> {
+        let mut remaining = idx;
+        let mut stack = vec![self];
+        while let Some(scope) = stack.pop() {
+            if remaining == 0 {
+                return Some(scope);
+            }
+            remaining -= 1;
+            stack.extend(scope.children.iter().rev());
+        }
+        None
+    }
+}
+
+/// References a scope in the original scope tree of a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OriginalScopeRef {
+    /// the source identifier
+    pub src_id: u32,
+    /// the index of the scope in a pre-order walk of the source's scope tree
+    pub scope_idx: u32,
+}
+
+/// Represents the location an inlined function was called from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Callsite {
+    /// source identifier
+    pub src_id: u32,
+    /// the source line number (0-indexed)
+    pub src_line: u32,
+    /// the source column number (0-indexed)
+    pub src_col: u32,
+}
+
+/// Represents a frame in the original source.
+///
+/// Frames are produced by expanding the ranges that cover a position in the
+/// generated file, which can yield more than one frame when functions were
+/// inlined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OriginalFrame<'a> {
+    /// source identifier
+    pub src_id: u32,
+    /// the source if it exists as string
+    pub source: Option<&'a str>,
+    /// the source line number (0-indexed)
+    pub src_line: u32,
+    /// the source column number (0-indexed)
+    pub src_col: u32,
+    /// the name of the function the frame belongs to if known
+    pub name: Option<&'a str>,
+}
+
+/// Represents a range in the generated file.
+///
+/// Positions are 0-indexed line and column pairs in the generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedRange {
+    /// the position where the range starts
+    pub start: (u32, u32),
+    /// the position where the range ends
+    pub end: (u32, u32),
+    /// the original scope this range was generated from
+    pub definition: Option<OriginalScopeRef>,
+    /// the location the original scope was inlined at
+    pub callsite: Option<Callsite>,
+    /// true if this range shows up as a frame in stack traces
+    pub is_stack_frame: bool,
+    /// true if this range has no equivalent in the original source
+    pub is_hidden: bool,
+    /// the expressions that hold the values of the definition's variables
+    pub bindings: Vec<Option<Arc<str>>>,
+    /// the ranges nested in this range
+    pub children: Vec<GeneratedRange>,
+}
+
+impl GeneratedRange {
+    /// Creates a new generated range without any metadata or children.
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> GeneratedRange {
+        GeneratedRange {
+            start,
+            end,
+            definition: None,
+            callsite: None,
+            is_stack_frame: false,
+            is_hidden: false,
+            bindings: vec![],
+            children: vec![],
+        }
+    }
+
+    /// Returns `true` if the given position falls into this range.
+    pub fn contains(&self, line: u32, col: u32) -> bool {
+        self.start <= (line, col) && (line, col) < self.end
+    }
+
+    /// Rewrites all source references in this range and its children.
+    ///
+    /// References for which `f` returns `None` are removed.
+    pub(crate) fn remap_sources<F: Fn(u32) -> Option<u32>>(&mut self, f: &F) {
+        self.definition = self.definition.and_then(|def| {
+            Some(OriginalScopeRef {
+                src_id: f(def.src_id)?,
+                ..def
+            })
+        });
+        self.callsite = self.callsite.and_then(|callsite| {
+            Some(Callsite {
+                src_id: f(callsite.src_id)?,
+                ..callsite
+            })
+        });
+        for child in self.children.iter_mut() {
+            child.remap_sources(f);
+        }
+    }
+
+    /// Moves this range and its children by the given offset.
+    ///
+    /// Columns are only adjusted on the first line, just like for sections
+    /// of an index sourcemap.
+    pub(crate) fn offset(&mut self, off_line: u32, off_col: u32) {
+        let adjust = |(line, col): (u32, u32)| {
+            if line == 0 {
+                (off_line, col + off_col)
+            } else {
+                (line + off_line, col)
+            }
+        };
+        self.start = adjust(self.start);
+        self.end = adjust(self.end);
+        for child in self.children.iter_mut() {
+            child.offset(off_line, off_col);
+        }
+    }
+}
+
+fn resolve_name(names: &[Arc<str>], idx: i64) -> Option<Arc<str>> {
+    names.get(usize::try_from(idx).ok()?).cloned()
+}
+
+fn attach<T>(stack: &mut [T], roots: &mut Vec<T>, item: T, children: fn(&mut T) -> &mut Vec<T>) {
+    match stack.last_mut() {
+        Some(parent) => children(parent).push(item),
+        None => roots.push(item),
+    }
+}
+
+/// Decodes the scope tree of a single source.
+///
+/// Returns `None` if the encoded data is malformed.
+pub(crate) fn decode_original_scope(encoded: &str, names: &[Arc<str>]) -> Option<OriginalScope> {
+    let mut stack: Vec<OriginalScope> = vec![];
+    let mut roots = vec![];
+    let mut nums = Vec::with_capacity(8);
+    let mut line = 0;
+    let mut name_id = 0;
+
+    for item in encoded.split(',') {
+        if item.is_empty() {
+            continue;
+        }
+
+        nums.clear();
+        parse_vlq_segment_into(item, &mut nums).ok()?;
+        let mut nums = nums.iter().copied();
+
+        line += nums.next()?;
+        let pos = (u32::try_from(line).ok()?, u32::try_from(nums.next()?).ok()?);
+
+        match nums.next() {
+            Some(flags) => {
+                let mut scope = OriginalScope::new(pos, pos);
+                scope.is_stack_frame = flags & ORIGINAL_SCOPE_IS_STACK_FRAME != 0;
+                if flags & ORIGINAL_SCOPE_HAS_KIND != 0 {
+                    name_id += nums.next()?;
+                    scope.kind = Some(resolve_name(names, name_id)?);
+                }
+                if flags & ORIGINAL_SCOPE_HAS_NAME != 0 {
+                    name_id += nums.next()?;
+                    scope.name = Some(resolve_name(names, name_id)?);
+                }
+                for delta in nums {
+                    name_id += delta;
+                    scope.variables.push(resolve_name(names, name_id)?);
+                }
+                stack.push(scope);
+            }
+            None => {
+                let mut scope = stack.pop()?;
+                scope.end = pos;
+                attach(&mut stack, &mut roots, scope, |s| &mut s.children);
+            }
+        }
+    }
+
+    if !stack.is_empty() || roots.len() > 1 {
+        return None;
+    }
+    roots.pop()
+}
+
+/// Decodes the generated ranges of a sourcemap.
+///
+/// Returns `None` if the encoded data is malformed.
+pub(crate) fn decode_generated_ranges(
+    encoded: &str,
+    names: &[Arc<str>],
+    source_count: u32,
+) -> Option<Vec<GeneratedRange>> {
+    let mut stack: Vec<GeneratedRange> = vec![];
+    let mut roots = vec![];
+    let mut nums = Vec::with_capacity(8);
+
+    let mut def_src_id = 0;
+    let mut def_scope_idx = 0;
+    let mut callsite_src_id = 0;
+    let mut callsite_line = 0;
+    let mut callsite_col = 0;
+
+    let check_src_id = |src_id: i64| u32::try_from(src_id).ok().filter(|&id| id < source_count);
+
+    for (line, encoded_line) in encoded.split(';').enumerate() {
+        let mut col = 0;
+
+        for item in encoded_line.split(',') {
+            if item.is_empty() {
+                continue;
+            }
+
+            nums.clear();
+            parse_vlq_segment_into(item, &mut nums).ok()?;
+            let mut nums = nums.iter().copied();
+
+            col += nums.next()?;
+            let pos = (line as u32, u32::try_from(col).ok()?);
+
+            match nums.next() {
+                Some(flags) => {
+                    let mut range = GeneratedRange::new(pos, pos);
+                    range.is_stack_frame = flags & GENERATED_RANGE_IS_STACK_FRAME != 0;
+                    range.is_hidden = flags & GENERATED_RANGE_IS_HIDDEN != 0;
+
+                    if flags & GENERATED_RANGE_HAS_DEFINITION != 0 {
+                        let src_delta = nums.next()?;
+                        if src_delta != 0 {
+                            def_src_id += src_delta;
+                            def_scope_idx = 0;
+                        }
+                        def_scope_idx += nums.next()?;
+                        range.definition = Some(OriginalScopeRef {
+                            src_id: check_src_id(def_src_id)?,
+                            scope_idx: u32::try_from(def_scope_idx).ok()?,
+                        });
+                    }
+
+                    if flags & GENERATED_RANGE_HAS_CALLSITE != 0 {
+                        let src_delta = nums.next()?;
+                        if src_delta != 0 {
+                            callsite_src_id += src_delta;
+                            callsite_line = 0;
+                        }
+                        let line_delta = nums.next()?;
+                        if src_delta != 0 || line_delta != 0 {
+                            callsite_line += line_delta;
+                            callsite_col = 0;
+                        }
+                        callsite_col += nums.next()?;
+                        range.callsite = Some(Callsite {
+                            src_id: check_src_id(callsite_src_id)?,
+                            src_line: u32::try_from(callsite_line).ok()?,
+                            src_col: u32::try_from(callsite_col).ok()?,
+                        });
+                    }
+
+                    for binding in nums {
+                        range.bindings.push(if binding == -1 {
+                            None
+                        } else {
+                            Some(resolve_name(names, binding)?)
+                        });
+                    }
+                    stack.push(range);
+                }
+                None => {
+                    let mut range = stack.pop()?;
+                    range.end = pos;
+                    attach(&mut stack, &mut roots, range, |r| &mut r.children);
+                }
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return None;
+    }
+    Some(roots)
+}
+
+/// Assigns indexes to names referenced by scopes, extending the name list
+/// as needed.
+struct NameTable<'a> {
+    names: &'a mut Vec<Arc<str>>,
+    lookup: FxHashMap<Arc<str>, u32>,
+}
+
+impl<'a> NameTable<'a> {
+    fn new(names: &'a mut Vec<Arc<str>>) -> NameTable<'a> {
+        let mut lookup = FxHashMap::default();
+        for (idx, name) in names.iter().enumerate() {
+            lookup.entry(name.clone()).or_insert(idx as u32);
+        }
+        NameTable { names, lookup }
+    }
+
+    fn get_or_insert(&mut self, name: &Arc<str>) -> i64 {
+        let count = self.names.len() as u32;
+        let id = *self.lookup.entry(name.clone()).or_insert(count);
+        if id == count {
+            self.names.push(name.clone());
+        }
+        i64::from(id)
+    }
+}
+
+fn encode_original_scope(
+    out: &mut String,
+    scope: &OriginalScope,
+    names: &mut NameTable<'_>,
+    prev_line: &mut i64,
+    prev_name_id: &mut i64,
+) {
+    if !out.is_empty() {
+        out.push(',');
+    }
+    let (line, col) = scope.start;
+    encode_vlq(out, i64::from(line) - *prev_line);
+    encode_vlq(out, i64::from(col));
+    *prev_line = i64::from(line);
+
+    let mut flags = 0;
+    if scope.name.is_some() {
+        flags |= ORIGINAL_SCOPE_HAS_NAME;
+    }
+    if scope.kind.is_some() {
+        flags |= ORIGINAL_SCOPE_HAS_KIND;
+    }
+    if scope.is_stack_frame {
+        flags |= ORIGINAL_SCOPE_IS_STACK_FRAME;
+    }
+    encode_vlq(out, flags);
+
+    for name in scope
+        .kind
+        .iter()
+        .chain(scope.name.iter())
+        .chain(scope.variables.iter())
+    {
+        let name_id = names.get_or_insert(name);
+        encode_vlq(out, name_id - *prev_name_id);
+        *prev_name_id = name_id;
+    }
+
+    for child in scope.children.iter() {
+        encode_original_scope(out, child, names, prev_line, prev_name_id);
+    }
+
+    let (line, col) = scope.end;
+    out.push(',');
+    encode_vlq(out, i64::from(line) - *prev_line);
+    encode_vlq(out, i64::from(col));
+    *prev_line = i64::from(line);
+}
+
+#[derive(Default)]
+struct GeneratedRangeEncoder {
+    out: String,
+    line: u32,
+    col: u32,
+    def_src_id: i64,
+    def_scope_idx: i64,
+    callsite_src_id: i64,
+    callsite_line: i64,
+    callsite_col: i64,
+}
+
+impl GeneratedRangeEncoder {
+    fn encode_position(&mut self, (line, col): (u32, u32)) {
+        debug_assert!(
+            (line, col) >= (self.line, self.col),
+            "generated range positions must not go backwards"
+        );
+        if line != self.line {
+            while self.line < line {
+                self.out.push(';');
+                self.line += 1;
+            }
+            self.col = 0;
+        } else if !self.out.is_empty() && !self.out.ends_with(';') {
+            self.out.push(',');
+        }
+        encode_vlq(&mut self.out, i64::from(col) - i64::from(self.col));
+        self.col = col;
+    }
+
+    fn encode(&mut self, range: &GeneratedRange, names: &mut NameTable<'_>) {
+        self.encode_position(range.start);
+
+        let mut flags = 0;
+        if range.definition.is_some() {
+            flags |= GENERATED_RANGE_HAS_DEFINITION;
+        }
+        if range.callsite.is_some() {
+            flags |= GENERATED_RANGE_HAS_CALLSITE;
+        }
+        if range.is_stack_frame {
+            flags |= GENERATED_RANGE_IS_STACK_FRAME;
+        }
+        if range.is_hidden {
+            flags |= GENERATED_RANGE_IS_HIDDEN;
+        }
+        encode_vlq(&mut self.out, flags);
+
+        if let Some(def) = range.definition {
+            let src_id = i64::from(def.src_id);
+            let scope_idx = i64::from(def.scope_idx);
+            encode_vlq(&mut self.out, src_id - self.def_src_id);
+            if src_id != self.def_src_id {
+                self.def_src_id = src_id;
+                self.def_scope_idx = 0;
+            }
+            encode_vlq(&mut self.out, scope_idx - self.def_scope_idx);
+            self.def_scope_idx = scope_idx;
+        }
+
+        if let Some(callsite) = range.callsite {
+            let src_id = i64::from(callsite.src_id);
+            let line = i64::from(callsite.src_line);
+            let col = i64::from(callsite.src_col);
+            let src_changed = src_id != self.callsite_src_id;
+            encode_vlq(&mut self.out, src_id - self.callsite_src_id);
+            if src_changed {
+                self.callsite_src_id = src_id;
+                self.callsite_line = 0;
+            }
+            encode_vlq(&mut self.out, line - self.callsite_line);
+            if src_changed || line != self.callsite_line {
+                self.callsite_line = line;
+                self.callsite_col = 0;
+            }
+            encode_vlq(&mut self.out, col - self.callsite_col);
+            self.callsite_col = col;
+        }
+
+        for binding in range.bindings.iter() {
+            let name_id = match binding {
+                Some(name) => names.get_or_insert(name),
+                None => -1,
+            };
+            encode_vlq(&mut self.out, name_id);
+        }
+
+        for child in range.children.iter() {
+            self.encode(child, names);
+        }
+
+        self.encode_position(range.end);
+    }
+}
+
+/// Sorts sibling ranges by their start position.
+pub(crate) fn sort_generated_ranges(ranges: &mut [GeneratedRange]) {
+    ranges.sort_by_key(|range| range.start);
+    for range in ranges.iter_mut() {
+        sort_generated_ranges(&mut range.children);
+    }
+}
+
+/// Checks that sorted ranges do not overlap and nest in their parent.
+///
+/// The encoding only supports positions that never go backwards.
+fn generated_ranges_are_valid(
+    ranges: &[GeneratedRange],
+    mut pos: (u32, u32),
+    parent_end: Option<(u32, u32)>,
+) -> bool {
+    for range in ranges {
+        if range.start < pos
+            || range.end < range.start
+            || parent_end.is_some_and(|end| range.end > end)
+            || !generated_ranges_are_valid(&range.children, range.start, Some(range.end))
+        {
+            return false;
+        }
+        pos = range.end;
+    }
+    true
+}
+
+/// Returns the sorted ranges without those that overlap an earlier sibling
+/// or do not nest in their parent.
+fn encodable_generated_ranges(
+    ranges: &[GeneratedRange],
+    mut pos: (u32, u32),
+    parent_end: Option<(u32, u32)>,
+) -> Vec<GeneratedRange> {
+    let mut rv = vec![];
+    for range in ranges {
+        if range.start < pos
+            || range.end < range.start
+            || parent_end.is_some_and(|end| range.end > end)
+        {
+            continue;
+        }
+        let mut range = range.clone();
+        range.children = encodable_generated_ranges(&range.children, range.start, Some(range.end));
+        pos = range.end;
+        rv.push(range);
+    }
+    rv
+}
+
+/// Serializes the scope information of a sourcemap.
+///
+/// Names referenced by scopes that are not yet part of `names` are appended
+/// to it.  Returns the values for `originalScopes` and `generatedRanges`.
+///
+/// Ranges that overlap an earlier sibling or do not nest in their parent
+/// cannot be represented and are left out together with their children,
+/// the same way the decoder ignores them.
+pub(crate) fn encode_scopes(
+    sm: &SourceMap,
+    names: &mut Vec<Arc<str>>,
+) -> (Option<Vec<Option<String>>>, Option<String>) {
+    if !sm.has_scopes() {
+        return (None, None);
+    }
+
+    let mut names = NameTable::new(names);
+
+    let original_scopes = if sm.original_scopes.iter().any(Option::is_some) {
+        let mut encoded: Vec<_> = sm
+            .original_scopes
+            .iter()
+            .map(|scope| {
+                scope.as_ref().map(|scope| {
+                    let mut out = String::new();
+                    encode_original_scope(&mut out, scope, &mut names, &mut 0, &mut 0);
+                    out
+                })
+            })
+            .collect();
+        encoded.resize(sm.get_source_count() as usize, None);
+        Some(encoded)
+    } else {
+        None
+    };
+
+    let encodable;
+    let mut ranges = &sm.generated_ranges[..];
+    if !generated_ranges_are_valid(ranges, (0, 0), None) {
+        encodable = encodable_generated_ranges(ranges, (0, 0), None);
+        ranges = &encodable;
+    }
+
+    let generated_ranges = if ranges.is_empty() {
+        None
+    } else {
+        let mut encoder = GeneratedRangeEncoder::default();
+        for range in ranges {
+            encoder.encode(range, &mut names);
+        }
+        Some(encoder.out)
+    };
+
+    (original_scopes, generated_ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<Arc<str>> {
+        names.iter().map(|&name| name.into()).collect()
+    }
+
+    #[test]
+    fn test_decode_original_scope() {
+        let names = names(&["module", "function", "foo", "x", "y"]);
+        let scope = decode_original_scope("AAEA,CAOCCCC,EC,GA", &names).unwrap();
+
+        assert_eq!(scope.start, (0, 0));
+        assert_eq!(scope.end, (6, 0));
+        assert_eq!(scope.get_kind(), Some("module"));
+        assert_eq!(scope.children.len(), 1);
+
+        let child = &scope.children[0];
+        assert_eq!(child.start, (1, 0));
+        assert_eq!(child.end, (3, 1));
+        assert_eq!(child.get_kind(), Some("function"));
+        assert_eq!(child.get_name(), Some("foo"));
+        assert!(child.is_stack_frame);
+        assert_eq!(child.variables, vec![Arc::from("x"), Arc::from("y")]);
+
+        assert_eq!(scope.get_scope_by_index(1), Some(child));
+        assert_eq!(scope.get_scope_by_index(2), None);
+    }
+
+    #[test]
+    fn test_decode_original_scope_unbalanced() {
+        let names = names(&["module"]);
+        assert_eq!(decode_original_scope("AAEA", &names), None);
+        assert_eq!(decode_original_scope("AAEA,CA,CA", &names), None);
+        assert_eq!(decode_original_scope("AACC", &names), None);
+    }
+
+    #[test]
+    fn test_decode_generated_ranges() {
+        let names = names(&["a"]);
+        let ranges = decode_generated_ranges("ASAA,EOACAEAA,C;E", &names, 1).unwrap();
+
+        assert_eq!(ranges.len(), 1);
+        let range = &ranges[0];
+        assert_eq!(range.start, (0, 0));
+        assert_eq!(range.end, (1, 2));
+        assert_eq!(
+            range.definition,
+            Some(OriginalScopeRef {
+                src_id: 0,
+                scope_idx: 0
+            })
+        );
+        assert!(range.is_hidden);
+
+        let inlined = &range.children[0];
+        assert_eq!(inlined.start, (0, 2));
+        assert_eq!(inlined.end, (0, 3));
+        assert_eq!(
+            inlined.definition,
+            Some(OriginalScopeRef {
+                src_id: 0,
+                scope_idx: 1
+            })
+        );
+        assert_eq!(
+            inlined.callsite,
+            Some(Callsite {
+                src_id: 0,
+                src_line: 2,
+                src_col: 0
+            })
+        );
+        assert!(inlined.is_stack_frame);
+        assert_eq!(inlined.bindings, vec![Some(Arc::from("a"))]);
+
+        assert_eq!(decode_generated_ranges("ASAA", &names, 1), None);
+        assert_eq!(decode_generated_ranges("ACCA,C", &names, 1), None);
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::str;
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::{Mutex, OnceLock};
 
 use if_chain::if_chain;
 
@@ -127,6 +127,7 @@ impl<'a> Iterator for Lines<'a> {
 pub struct SourceView {
     source: Arc<str>,
     line_end_offsets: Mutex<Vec<LineEndOffset>>,
+    utf16_line_starts: OnceLock<Vec<u32>>,
 }
 
 impl Clone for SourceView {
@@ -134,6 +135,7 @@ impl Clone for SourceView {
         SourceView {
             source: self.source.clone(),
             line_end_offsets: Mutex::new(vec![]),
+            utf16_line_starts: OnceLock::new(),
         }
     }
 }
@@ -158,6 +160,7 @@ impl SourceView {
         SourceView {
             source,
             line_end_offsets: Mutex::new(vec![]),
+            utf16_line_starts: OnceLock::new(),
         }
     }
 
@@ -166,6 +169,7 @@ impl SourceView {
         SourceView {
             source: source.into(),
             line_end_offsets: Mutex::new(vec![]),
+            utf16_line_starts: OnceLock::new(),
         }
     }
 
@@ -318,6 +322,50 @@ impl SourceView {
         None
     }
 
+    /// Returns the UTF-16 offsets at which the lines start.
+    fn get_utf16_line_starts(&self) -> &[u32] {
+        self.utf16_line_starts.get_or_init(|| {
+            let mut rv = vec![];
+            let mut offset = 0;
+            let mut byte_offset = 0;
+            for line in self.lines() {
+                let line_start = line.as_ptr() as usize - self.source.as_ptr() as usize;
+                offset += self.source[byte_offset..line_start].encode_utf16().count() as u32;
+                byte_offset = line_start;
+                rv.push(offset);
+            }
+            rv
+        })
+    }
+
+    /// Converts a UTF-16 offset into the source into a 0-indexed line and
+    /// column.
+    ///
+    /// Offsets within a line terminator belong to the line it terminates
+    /// and offsets past the end of the source to the last line.
+    ///
+    /// ```rust
+    /// use sourcemap::SourceView;
+    /// let view = SourceView::from_string("a\r\nä();".into());
+    /// assert_eq!(view.offset_to_position(4), (1, 1));
+    /// assert_eq!(view.position_to_offset(1, 1), Some(4));
+    /// ```
+    pub fn offset_to_position(&self, offset: u32) -> (u32, u32) {
+        let line_starts = self.get_utf16_line_starts();
+        let line = line_starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        (line as u32, offset - line_starts[line])
+    }
+
+    /// Converts a 0-indexed line and column into a UTF-16 offset into the
+    /// source.
+    ///
+    /// The column is not checked against the length of the line.
+    pub fn position_to_offset(&self, line: u32, col: u32) -> Option<u32> {
+        Some(self.get_utf16_line_starts().get(line as usize)? + col)
+    }
+
     /// Returns the number of lines.
     pub fn line_count(&self) -> usize {
         self.get_line(!0);
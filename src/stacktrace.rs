@@ -0,0 +1,361 @@
+//! Parses and symbolicates JavaScript stack traces.
+//!
+//! Stack traces as found in `Error.prototype.stack` are parsed into
+//! [`StackFrame`]s.  The formats of V8 (Chrome, Node), SpiderMonkey
+//! (Firefox) and JavaScriptCore (Safari) are supported.  A [`Symbolicator`]
+//! then maps the frames to their original locations with sourcemaps
+//! provided by a [`SourceMapLoader`].  Every [`Resolver`] is a
+//! [`SourceMapLoader`] that follows the `sourceMappingURL` of each script.
+//!
+//! Line and column numbers in stack traces are 1-indexed, and so are all
+//! line and column numbers in this module.  The conversion to the 0-indexed
+//! positions used by sourcemaps happens internally.
+//!
+//! ```rust
+//! use sourcemap::stacktrace::Symbolicator;
+//! use sourcemap::{Resolver, Result};
+//!
+//! struct BundleResolver;
+//!
+//! impl Resolver for BundleResolver {
+//!     fn fetch(&mut self, url: &str) -> Result<Option<Vec<u8>>> {
+//!         Ok(match url {
+//!             "https://example.com/bundle.js" => {
+//!                 Some(b"foo()\n//# sourceMappingURL=bundle.js.map".to_vec())
+//!             }
+//!             "https://example.com/bundle.js.map" => Some(
+//!                 br#"{"version":3,"sources":["src/foo.ts"],"names":["foo"],"mappings":"UAyCIA"}"#
+//!                     .to_vec(),
+//!             ),
+//!             _ => None,
+//!         })
+//!     }
+//! }
+//!
+//! let stack = "TypeError: x is undefined
+//!     at foo (https://example.com/bundle.js:1:11)";
+//!
+//! let mut symbolicator = Symbolicator::new(BundleResolver);
+//! let frames = symbolicator.symbolicate(stack);
+//! assert_eq!(frames[0].source.as_deref(), Some("src/foo.ts"));
+//! assert_eq!((frames[0].line, frames[0].column), (Some(42), Some(5)));
+//! ```
+//!
+//! [`Resolver`]: crate::Resolver
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::sourceview::SourceView;
+use crate::types::DecodedMap;
+
+/// A frame of a parsed stack trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// the function name as printed in the stack trace
+    pub function: Option<String>,
+    /// the URL of the script, empty for frames without a location
+    pub url: String,
+    /// the 1-indexed line number
+    pub line: Option<u32>,
+    /// the 1-indexed column number
+    pub column: Option<u32>,
+}
+
+impl fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function.as_deref().unwrap_or("<unknown>"))?;
+        write!(f, " ({}", self.url)?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Splits `url:line:col` or `url:line` into its parts.
+fn parse_location(location: &str) -> Option<(&str, u32, Option<u32>)> {
+    let (rest, last) = location.rsplit_once(':')?;
+    let last = last.parse().ok()?;
+    match rest.rsplit_once(':') {
+        Some((url, line)) => match line.parse() {
+            Ok(line) => Some((url, line, Some(last))),
+            Err(_) => Some((rest, last, None)),
+        },
+        None => Some((rest, last, None)),
+    }
+}
+
+fn make_frame(function: Option<&str>, location: &str) -> StackFrame {
+    let function = function
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(str::to_string);
+    match parse_location(location) {
+        Some((url, line, column)) => StackFrame {
+            function,
+            url: url.to_string(),
+            line: Some(line),
+            column,
+        },
+        None => StackFrame {
+            function,
+            url: location.to_string(),
+            line: None,
+            column: None,
+        },
+    }
+}
+
+/// Parses a V8 frame such as `at foo (https://example.com/a.js:1:2)`.
+fn parse_v8_frame(line: &str) -> Option<StackFrame> {
+    let rest = line.strip_prefix("at ")?;
+    let rest = rest.strip_prefix("async ").unwrap_or(rest);
+
+    let (function, location) = match rest.strip_suffix(')') {
+        Some(inner) => match inner.find(" (") {
+            Some(idx) => (Some(&inner[..idx]), &inner[idx + 2..]),
+            None => (None, rest),
+        },
+        None => (None, rest),
+    };
+
+    // `eval at foo (https://example.com/a.js:1:2), <anonymous>:1:1`
+    let location = match location.strip_prefix("eval at ") {
+        Some(eval) => eval
+            .rsplit_once('(')
+            .and_then(|(_, location)| location.split(')').next())
+            .unwrap_or(location),
+        None => location,
+    };
+
+    Some(make_frame(function, location))
+}
+
+/// Parses a SpiderMonkey or JavaScriptCore frame such as
+/// `foo@https://example.com/a.js:1:2`.
+fn parse_gecko_frame(line: &str) -> Option<StackFrame> {
+    let (function, location) = line.split_once('@')?;
+
+    // JavaScriptCore prints frames without function names as bare
+    // locations, which can contain an `@` themselves, for instance
+    // `https://esm.sh/@babel/core.js:1:2` or
+    // `https://unpkg.com/react@18.2.0/index.js:1:2`.
+    if function.contains("://")
+        || function.ends_with('/')
+        || (parse_location(location).is_none() && parse_location(line).is_some())
+    {
+        return None;
+    }
+
+    // `https://example.com/a.js line 2 > eval:1:1`
+    if let Some((url, rest)) = location.split_once(" line ") {
+        let line = rest.split(' ').next().and_then(|line| line.parse().ok());
+        return Some(StackFrame {
+            function: Some(function).filter(|f| !f.is_empty()).map(str::to_string),
+            url: url.to_string(),
+            line,
+            column: None,
+        });
+    }
+
+    Some(make_frame(Some(function), location))
+}
+
+/// Parses a stack trace into frames.
+///
+/// Lines that are not recognized as frames, such as the error message at
+/// the top of V8 stack traces, are skipped.  The frames are returned in the
+/// order of the stack trace, innermost first.
+///
+/// ```rust
+/// use sourcemap::stacktrace::parse_stacktrace;
+/// let frames = parse_stacktrace("foo@https://example.com/a.js:1:2\n@https://example.com/a.js:3:4");
+/// assert_eq!(frames[0].function.as_deref(), Some("foo"));
+/// assert_eq!(frames[1].function, None);
+/// assert_eq!((frames[1].line, frames[1].column), (Some(3), Some(4)));
+/// ```
+pub fn parse_stacktrace(stack: &str) -> Vec<StackFrame> {
+    stack
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            if line.starts_with("at ") {
+                parse_v8_frame(line)
+            } else if let Some(frame) = parse_gecko_frame(line) {
+                Some(frame)
+            } else {
+                // JavaScriptCore prints frames without function names as
+                // bare locations.
+                parse_location(line).map(|_| make_frame(None, line))
+            }
+        })
+        .collect()
+}
+
+/// Loads sourcemaps for the scripts referenced by stack frames.
+///
+/// This is implemented for every [`Resolver`](crate::Resolver).
+pub trait SourceMapLoader {
+    /// Loads the sourcemap for the script at the given URL.
+    fn load_for_url(&mut self, url: &str) -> Option<DecodedMap>;
+
+    /// Loads the source of the (minified) script at the given URL.
+    ///
+    /// This is used to guess original function names if the sourcemap has
+    /// no scope information.  The default implementation returns `None`.
+    fn load_minified_source(&mut self, url: &str) -> Option<SourceView> {
+        let _ = url;
+        None
+    }
+}
+
+/// A stack frame mapped to its original location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolicatedFrame {
+    /// the frame as it was parsed from the stack trace
+    pub raw: StackFrame,
+    /// the original source file
+    pub source: Option<String>,
+    /// the 1-indexed original line number
+    pub line: Option<u32>,
+    /// the 1-indexed original column number
+    pub column: Option<u32>,
+    /// the original function name, if it could be determined
+    pub function: Option<String>,
+    /// the lines of the original source before `context_line`
+    pub pre_context: Vec<String>,
+    /// the original source line of the frame
+    pub context_line: Option<String>,
+    /// the lines of the original source after `context_line`
+    pub post_context: Vec<String>,
+}
+
+impl SymbolicatedFrame {
+    fn unmapped(raw: &StackFrame) -> SymbolicatedFrame {
+        SymbolicatedFrame {
+            raw: raw.clone(),
+            source: None,
+            line: None,
+            column: None,
+            function: None,
+            pre_context: vec![],
+            context_line: None,
+            post_context: vec![],
+        }
+    }
+
+    /// Returns `true` if the frame was mapped to an original location.
+    pub fn is_symbolicated(&self) -> bool {
+        self.source.is_some()
+    }
+}
+
+struct LoadedMap {
+    sm: DecodedMap,
+    minified_source: Option<SourceView>,
+}
+
+/// Symbolicates stack traces with sourcemaps from a [`SourceMapLoader`].
+///
+/// Sourcemaps are loaded once per URL and kept for the lifetime of the
+/// symbolicator.
+pub struct Symbolicator<L> {
+    loader: L,
+    context_lines: u32,
+    maps: HashMap<String, Option<LoadedMap>>,
+}
+
+impl<L: SourceMapLoader> Symbolicator<L> {
+    /// Creates a new symbolicator that uses the given loader.
+    pub fn new(loader: L) -> Symbolicator<L> {
+        Symbolicator {
+            loader,
+            context_lines: 5,
+            maps: HashMap::new(),
+        }
+    }
+
+    /// Sets the number of context lines to include before and after the
+    /// line of a frame.  The default is 5.
+    pub fn set_context_lines(&mut self, context_lines: u32) {
+        self.context_lines = context_lines;
+    }
+
+    /// Returns the number of context lines.
+    pub fn get_context_lines(&self) -> u32 {
+        self.context_lines
+    }
+
+    /// Parses and symbolicates a stack trace.
+    pub fn symbolicate(&mut self, stack: &str) -> Vec<SymbolicatedFrame> {
+        parse_stacktrace(stack)
+            .iter()
+            .map(|frame| self.symbolicate_frame(frame))
+            .collect()
+    }
+
+    /// Symbolicates a single frame.
+    ///
+    /// Frames that cannot be mapped are returned without original location.
+    pub fn symbolicate_frame(&mut self, frame: &StackFrame) -> SymbolicatedFrame {
+        let mut rv = SymbolicatedFrame::unmapped(frame);
+        let (line, column) = match (frame.line, frame.column) {
+            (Some(line), column) if line > 0 => (line - 1, column.unwrap_or(1).saturating_sub(1)),
+            _ => return rv,
+        };
+
+        let loader = &mut self.loader;
+        let loaded = self.maps.entry(frame.url.clone()).or_insert_with(|| {
+            let sm = loader.load_for_url(&frame.url)?;
+            let minified_source = loader.load_minified_source(&frame.url);
+            Some(LoadedMap {
+                sm,
+                minified_source,
+            })
+        });
+        let loaded = match loaded {
+            Some(loaded) => loaded,
+            None => return rv,
+        };
+
+        let token = match loaded.sm.lookup_token(line, column) {
+            Some(token) if token.has_source() => token,
+            _ => return rv,
+        };
+        rv.source = token.get_source().map(str::to_string);
+        rv.line = Some(token.get_src_line() + 1);
+        rv.column = Some(token.get_src_col() + 1);
+
+        // Only the last segment of names like `Object.foo` or `new Foo` can
+        // be found in the minified source.
+        let minified_name = frame
+            .function
+            .as_deref()
+            .and_then(|name| name.rsplit(['.', ' ']).next());
+        rv.function = loaded
+            .sm
+            .get_original_function_name(
+                line,
+                column,
+                minified_name,
+                loaded.minified_source.as_ref(),
+            )
+            .map(str::to_string);
+
+        if let Some(view) = token.get_source_view() {
+            let src_line = token.get_src_line();
+            let start = src_line.saturating_sub(self.context_lines);
+            let get_line = |idx| view.get_line(idx).map(str::to_string);
+            rv.pre_context = (start..src_line).filter_map(get_line).collect();
+            rv.context_line = get_line(src_line);
+            rv.post_context = (src_line + 1..=src_line.saturating_add(self.context_lines))
+                .map_while(get_line)
+                .collect();
+        }
+
+        rv
+    }
+}
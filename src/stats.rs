@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::analysis::generated_lines;
+use crate::sourceview::SourceView;
+use crate::types::SourceMap;
+
+/// The number of longest unmapped runs reported by [`SourceMapStats`].
+const MAX_UNMAPPED_RUNS: usize = 10;
+
+/// A run of generated columns that is not mapped to a source.
+///
+/// Columns are 0-indexed and counted in UTF-16 code units.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmappedRun {
+    /// the generated line
+    pub line: u32,
+    /// the first unmapped column
+    pub start_col: u32,
+    /// the column after the last unmapped column
+    pub end_col: u32,
+}
+
+impl UnmappedRun {
+    /// Returns the number of unmapped columns.
+    pub fn len(&self) -> u32 {
+        self.end_col - self.start_col
+    }
+
+    /// Returns `true` if the run is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Quality metrics of a sourcemap.
+///
+/// Returned by [`SourceMap::stats`].  Columns and bytes are counted over
+/// the contents of all lines of the generated file, excluding line
+/// terminators and the `sourceMappingURL` comment.  A column is mapped if
+/// it is covered by a token with a source.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMapStats {
+    /// the number of tokens
+    pub token_count: u32,
+    /// the number of tokens per generated line
+    pub tokens_per_line: Vec<u32>,
+    /// the number of columns of the generated file
+    pub total_columns: u64,
+    /// the number of mapped columns
+    pub mapped_columns: u64,
+    /// the number of bytes of the generated file
+    pub total_bytes: u64,
+    /// the number of mapped bytes
+    pub mapped_bytes: u64,
+    /// the number of tokens that do not map to a source
+    pub tokens_without_source: u32,
+    /// the number of tokens that map to a source and carry a name
+    pub tokens_with_name: u32,
+    /// the number of names
+    pub name_count: u32,
+    /// the number of names referenced by at least one token
+    pub used_name_count: u32,
+    /// the sources without source contents
+    pub sources_without_content: Vec<String>,
+    /// the sources that are listed more than once
+    pub duplicate_sources: Vec<String>,
+    /// the longest unmapped runs, longest first
+    pub longest_unmapped_runs: Vec<UnmappedRun>,
+}
+
+fn percentage(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+impl SourceMapStats {
+    /// Returns the percentage of mapped columns.
+    pub fn mapped_columns_percentage(&self) -> f64 {
+        percentage(self.mapped_columns, self.total_columns)
+    }
+
+    /// Returns the percentage of mapped bytes.
+    pub fn mapped_bytes_percentage(&self) -> f64 {
+        percentage(self.mapped_bytes, self.total_bytes)
+    }
+
+    /// Returns the percentage of tokens with a source that carry a name.
+    pub fn names_coverage(&self) -> f64 {
+        let with_source = self.token_count - self.tokens_without_source;
+        percentage(self.tokens_with_name.into(), with_source.into())
+    }
+}
+
+pub(crate) fn sourcemap_stats(sm: &SourceMap, generated: &SourceView) -> SourceMapStats {
+    let mut stats = SourceMapStats {
+        token_count: sm.get_token_count(),
+        tokens_per_line: vec![],
+        total_columns: 0,
+        mapped_columns: 0,
+        total_bytes: 0,
+        mapped_bytes: 0,
+        tokens_without_source: 0,
+        tokens_with_name: 0,
+        name_count: sm.get_name_count(),
+        used_name_count: 0,
+        sources_without_content: vec![],
+        duplicate_sources: vec![],
+        longest_unmapped_runs: vec![],
+    };
+
+    let mut used_names = vec![false; sm.get_name_count() as usize];
+    for token in sm.tokens() {
+        let line = token.get_dst_line() as usize;
+        if stats.tokens_per_line.len() <= line {
+            stats.tokens_per_line.resize(line + 1, 0);
+        }
+        stats.tokens_per_line[line] += 1;
+        if !token.has_source() {
+            stats.tokens_without_source += 1;
+        } else if token.has_name() {
+            stats.tokens_with_name += 1;
+        }
+        if let Some(used) = used_names.get_mut(token.get_name_id() as usize) {
+            *used = true;
+        }
+    }
+    stats.used_name_count = used_names.iter().filter(|&&used| used).count() as u32;
+
+    let mut runs = vec![];
+    let mut line_count = 0;
+    for line in generated_lines(sm, generated) {
+        line_count += 1;
+
+        let mut run_start = None;
+        let mut col = 0;
+        for (chunk_col, token, chunk) in line.chunks {
+            let width: u32 = chunk.chars().map(|c| c.len_utf16() as u32).sum();
+            stats.total_columns += u64::from(width);
+            stats.total_bytes += chunk.len() as u64;
+            col = chunk_col + width;
+            if token.is_some_and(|token| token.has_source()) {
+                stats.mapped_columns += u64::from(width);
+                stats.mapped_bytes += chunk.len() as u64;
+                if let Some(start_col) = run_start.take() {
+                    runs.push(UnmappedRun {
+                        line: line.idx,
+                        start_col,
+                        end_col: chunk_col,
+                    });
+                }
+            } else if run_start.is_none() {
+                run_start = Some(chunk_col);
+            }
+        }
+        if let Some(start_col) = run_start {
+            runs.push(UnmappedRun {
+                line: line.idx,
+                start_col,
+                end_col: col,
+            });
+        }
+    }
+    if stats.tokens_per_line.len() < line_count {
+        stats.tokens_per_line.resize(line_count, 0);
+    }
+    runs.sort_by(|a, b| b.len().cmp(&a.len()).then(a.line.cmp(&b.line)));
+    runs.truncate(MAX_UNMAPPED_RUNS);
+    stats.longest_unmapped_runs = runs;
+
+    let mut seen = BTreeMap::new();
+    for src_id in 0..sm.get_source_count() {
+        let source = sm.get_source(src_id).unwrap_or_default();
+        if sm.get_source_contents(src_id).is_none() {
+            stats.sources_without_content.push(source.to_string());
+        }
+        let count = seen.entry(source).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            stats.duplicate_sources.push(source.to_string());
+        }
+    }
+
+    stats
+}
@@ -0,0 +1,300 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use debugid::DebugId;
+use serde_json::value::RawValue;
+
+use crate::decoder::{decode_borrowed_parts, MappingsIter};
+use crate::errors::Result;
+use crate::jsontypes::parse_json_str;
+use crate::types::{RawToken, SourceMap};
+
+/// Where a line starts in the mappings and the running values of the
+/// decoder at that point.
+#[derive(Debug, Clone, Copy)]
+struct LineStart {
+    offset: usize,
+    range_offset: usize,
+    state: (u32, u32, u32, u32),
+}
+
+/// Returns the byte offsets at which the `;` separated lines start.
+fn line_offsets(s: &str) -> impl Iterator<Item = usize> + '_ {
+    std::iter::once(0).chain(s.match_indices(';').map(|(idx, _)| idx + 1))
+}
+
+/// A sourcemap that is decoded with as little memory overhead as possible.
+///
+/// Unlike [`SourceMap`] this does not decode the `mappings` upfront but
+/// borrows them from the input buffer and decodes them on demand.  The
+/// `sourcesContent` is not parsed at all: only the location of each
+/// entry in the input buffer is remembered so it can be loaded lazily.
+/// Vendor extensions such as `x_facebook_sources` are skipped.
+///
+/// This is useful for processing very large sourcemaps where only a few
+/// lookups are needed:
+///
+/// ```rust
+/// use sourcemap::StreamingSourceMap;
+/// let input: &[_] = b"{
+///     \"version\":3,
+///     \"sources\":[\"coolstuff.js\"],
+///     \"sourcesContent\":[\"var x = 1;\\nalert(x);\"],
+///     \"names\":[\"x\",\"alert\"],
+///     \"mappings\":\"AAAA,GAAIA,GAAI,EACR,IAAIA,GAAK,EAAG,CACVC,MAAM\"
+/// }";
+/// let sm = StreamingSourceMap::from_slice(input).unwrap();
+/// let token = sm.lookup_token(0, 3).unwrap().unwrap();
+/// assert_eq!(sm.get_name(token.name_id), Some("x"));
+/// assert_eq!(
+///     sm.load_source_contents(0).unwrap().as_deref(),
+///     Some("var x = 1;\nalert(x);")
+/// );
+/// ```
+#[derive(Debug)]
+pub struct StreamingSourceMap<'a> {
+    file: Option<Arc<str>>,
+    source_root: Option<Arc<str>>,
+    sources: Vec<Arc<str>>,
+    sources_prefixed: Option<Vec<Arc<str>>>,
+    sources_content: Vec<Option<&'a RawValue>>,
+    names: Vec<Arc<str>>,
+    mappings: Cow<'a, str>,
+    range_mappings: Option<Cow<'a, str>>,
+    ignore_list: Vec<u32>,
+    debug_id: Option<DebugId>,
+    base: usize,
+    line_starts: OnceLock<Vec<LineStart>>,
+}
+
+impl<'a> StreamingSourceMap<'a> {
+    /// Decodes a sourcemap from a JSON byte slice in UTF-8 format.
+    ///
+    /// Optionally a "garbage header" as defined by the sourcemap draft
+    /// specification is supported.  In case an indexed sourcemap is
+    /// encountered an error is returned.
+    pub fn from_slice(slice: &'a [u8]) -> Result<StreamingSourceMap<'a>> {
+        let parts = decode_borrowed_parts(slice)?;
+        Ok(StreamingSourceMap {
+            file: parts.file.map(Into::into),
+            source_root: parts.source_root,
+            sources: parts.sources.into_iter().map(Into::into).collect(),
+            sources_prefixed: parts.sources_prefixed,
+            sources_content: parts
+                .sources_content
+                .into_iter()
+                .map(|raw| Some(raw).filter(|raw| raw.get() != "null"))
+                .collect(),
+            names: parts.names.into_iter().map(Into::into).collect(),
+            mappings: parts.mappings,
+            range_mappings: parts.range_mappings,
+            ignore_list: parts.ignore_list,
+            debug_id: parts.debug_id,
+            base: slice.as_ptr() as usize,
+            line_starts: OnceLock::new(),
+        })
+    }
+
+    /// Returns the embedded filename in case there is one.
+    pub fn get_file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns the embedded source_root in case there is one.
+    pub fn get_source_root(&self) -> Option<&str> {
+        self.source_root.as_deref()
+    }
+
+    /// Returns the embedded debug id.
+    pub fn get_debug_id(&self) -> Option<DebugId> {
+        self.debug_id
+    }
+
+    /// Returns the number of sources in the sourcemap.
+    pub fn get_source_count(&self) -> u32 {
+        self.sources.len() as u32
+    }
+
+    /// Looks up a source for a specific index.
+    ///
+    /// The `sourceRoot` is already applied to the returned value.
+    pub fn get_source(&self, idx: u32) -> Option<&str> {
+        let sources = self.sources_prefixed.as_deref().unwrap_or(&self.sources);
+        sources.get(idx as usize).map(|x| &x[..])
+    }
+
+    /// Returns the number of names in the sourcemap.
+    pub fn get_name_count(&self) -> u32 {
+        self.names.len() as u32
+    }
+
+    /// Looks up a name for a specific index.
+    pub fn get_name(&self, idx: u32) -> Option<&str> {
+        self.names.get(idx as usize).map(|x| &x[..])
+    }
+
+    /// Returns the source indexes that devtools should ignore.
+    pub fn ignore_list(&self) -> impl Iterator<Item = &u32> {
+        self.ignore_list.iter()
+    }
+
+    /// Returns an iterator decoding the tokens on the fly.
+    pub fn tokens(&self) -> MappingsIter<'_> {
+        MappingsIter::new(
+            &self.mappings,
+            self.range_mappings.as_deref(),
+            self.get_source_count(),
+            self.get_name_count(),
+        )
+    }
+
+    /// Decodes the mappings once to remember where every line starts.
+    fn line_starts(&self) -> Result<&[LineStart]> {
+        if let Some(starts) = self.line_starts.get() {
+            return Ok(starts);
+        }
+
+        let range_mappings = self.range_mappings.as_deref().unwrap_or_default();
+        let mut offsets = line_offsets(&self.mappings);
+        let mut range_offsets = line_offsets(range_mappings);
+        let mut next_start = |state| LineStart {
+            offset: offsets.next().unwrap_or(self.mappings.len()),
+            range_offset: range_offsets.next().unwrap_or(range_mappings.len()),
+            state,
+        };
+
+        let mut starts = vec![];
+        let mut state = (0, 0, 0, 0);
+        for token in self.tokens() {
+            let token = token?;
+            while starts.len() <= token.dst_line as usize {
+                starts.push(next_start(state));
+            }
+            if token.src_id != !0 {
+                state.0 = token.src_id;
+                state.1 = token.src_line;
+                state.2 = token.src_col;
+            }
+            if token.name_id != !0 {
+                state.3 = token.name_id;
+            }
+        }
+        let line_count = self.mappings.matches(';').count() + 1;
+        while starts.len() < line_count {
+            starts.push(next_start(state));
+        }
+
+        Ok(self.line_starts.get_or_init(|| starts))
+    }
+
+    /// Looks up the closest token to a given 0-indexed line and column.
+    ///
+    /// The first lookup decodes all mappings once to remember where every
+    /// line starts.  After that only the requested line is decoded, which
+    /// is still slower than [`SourceMap::lookup_token`].  Range mappings
+    /// are applied to the source column of the returned token.
+    pub fn lookup_token(&self, line: u32, col: u32) -> Result<Option<RawToken>> {
+        let starts = self.line_starts()?;
+        let last_line = starts.len() as u32 - 1;
+        let (mut cur_line, mut max_col) = if line > last_line {
+            (last_line, u32::MAX)
+        } else {
+            (line, col)
+        };
+
+        let rv = loop {
+            let start = starts[cur_line as usize];
+            let tokens = MappingsIter::new(
+                &self.mappings[start.offset..],
+                self.range_mappings
+                    .as_deref()
+                    .map(|range_mappings| &range_mappings[start.range_offset..]),
+                self.get_source_count(),
+                self.get_name_count(),
+            )
+            .resume_at(cur_line, start.state);
+
+            // columns within a line are not necessarily sorted, so the
+            // whole line is scanned for the closest token
+            let mut rv: Option<RawToken> = None;
+            for token in tokens {
+                let token = token?;
+                if token.dst_line != cur_line {
+                    break;
+                }
+                if token.dst_col <= max_col && rv.is_none_or(|rv| token.dst_col > rv.dst_col) {
+                    rv = Some(token);
+                }
+            }
+            if rv.is_some() || cur_line == 0 {
+                break rv;
+            }
+            // the closest token is the last one of an earlier line
+            cur_line -= 1;
+            max_col = u32::MAX;
+        };
+
+        Ok(rv.map(|mut token| {
+            if token.is_range && token.dst_line == line && token.src_col != !0 {
+                token.src_col += col - token.dst_col;
+            }
+            token
+        }))
+    }
+
+    /// Returns the byte range of the JSON string holding the contents of a
+    /// source within the buffer the sourcemap was decoded from.
+    ///
+    /// The range includes the surrounding quotes and the string is still
+    /// escaped.
+    pub fn get_source_contents_range(&self, idx: u32) -> Option<Range<usize>> {
+        let raw = self
+            .sources_content
+            .get(idx as usize)
+            .copied()
+            .flatten()?
+            .get();
+        let start = raw.as_ptr() as usize - self.base;
+        Some(start..start + raw.len())
+    }
+
+    /// Loads the contents of a source.
+    ///
+    /// The contents are only copied if they contain escape sequences.
+    pub fn load_source_contents(&self, idx: u32) -> Result<Option<Cow<'a, str>>> {
+        match self.sources_content.get(idx as usize).copied().flatten() {
+            Some(raw) => parse_json_str(raw),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts this into a fully decoded [`SourceMap`].
+    ///
+    /// Source contents are only loaded if `with_source_contents` is set.
+    pub fn to_sourcemap(&self, with_source_contents: bool) -> Result<SourceMap> {
+        let tokens = self.tokens().collect::<Result<Vec<_>>>()?;
+        let sources_content = if with_source_contents {
+            let contents = (0..self.sources_content.len() as u32)
+                .map(|idx| Ok(self.load_source_contents(idx)?.map(Into::into)))
+                .collect::<Result<Vec<_>>>()?;
+            Some(contents)
+        } else {
+            None
+        };
+
+        let mut sm = SourceMap::new(
+            self.file.clone(),
+            tokens,
+            self.names.clone(),
+            self.sources.clone(),
+            sources_content,
+        );
+        sm.set_source_root(self.source_root.clone());
+        sm.set_debug_id(self.debug_id);
+        for &idx in &self.ignore_list {
+            sm.add_to_ignore_list(idx);
+        }
+        Ok(sm)
+    }
+}
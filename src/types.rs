@@ -4,15 +4,24 @@ use std::collections::BTreeSet;
 use std::fmt;
 use std::io::{Read, Write};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
+use crate::binary::{decode_binary, encode_binary};
 use crate::builder::SourceMapBuilder;
 use crate::decoder::{decode, decode_slice};
-use crate::encoder::encode;
+use crate::encoder::{encode, encode_with_options, EncodeOptions};
 use crate::errors::{Error, Result};
+use crate::extensions::Extensions;
 use crate::hermes::SourceMapHermes;
+use crate::ignore::IgnoreRules;
+use crate::resolver::Resolver;
+use crate::scopes::{
+    sort_generated_ranges, GeneratedRange, OriginalFrame, OriginalScope, OriginalScopeRef,
+};
 use crate::sourceview::SourceView;
+use crate::stats::{sourcemap_stats, SourceMapStats};
 use crate::utils::{find_common_prefix, greatest_lower_bound};
+use crate::validate::{validate_decoded, validate_index, validate_sourcemap, Diagnostic};
 
 use debugid::DebugId;
 
@@ -23,6 +32,7 @@ use debugid::DebugId;
 /// * `with_names`: true
 /// * `with_source_contents`: true
 /// * `load_local_source_contents`: false
+/// * `ignore_rules`: None
 #[derive(Debug, Clone)]
 pub struct RewriteOptions<'a> {
     /// If enabled, names are kept in the rewritten sourcemap.
@@ -40,6 +50,9 @@ pub struct RewriteOptions<'a> {
     /// an item in the list is set to `~` then the common prefix
     /// of all sources is stripped.
     pub strip_prefixes: &'a [&'a str],
+    /// Optionally adds all sources that match the rules to the ignore
+    /// list.  Sources are matched before prefixes are stripped.
+    pub ignore_rules: Option<&'a IgnoreRules>,
 }
 
 impl<'a> Default for RewriteOptions<'a> {
@@ -51,6 +64,64 @@ impl<'a> Default for RewriteOptions<'a> {
             load_local_source_contents: false,
             base_path: None,
             strip_prefixes: &[][..],
+            ignore_rules: None,
+        }
+    }
+}
+
+/// Selects which token a lookup returns if no token starts exactly at the
+/// looked up position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bias {
+    /// The closest token before the position.
+    GreatestLowerBound,
+    /// The closest token after the position.
+    LeastUpperBound,
+}
+
+/// Controls the `lookup_token_with_options` behavior
+///
+/// Default configuration:
+///
+/// * `bias`: `Bias::GreatestLowerBound`
+/// * `same_line_only`: false
+/// * `max_column_distance`: None
+///
+/// The default configuration matches `lookup_token`.
+#[derive(Debug, Clone)]
+pub struct LookupOptions {
+    /// Which token to return if no token starts at the position.
+    pub bias: Bias,
+    /// If enabled, only tokens on the same line as the position are
+    /// returned.
+    pub same_line_only: bool,
+    /// Optionally the maximum number of columns between the position and
+    /// the token.  Tokens on other lines are never returned if this is set.
+    /// Range tokens before the position are not limited as they cover
+    /// every column up to the next token.
+    pub max_column_distance: Option<u32>,
+}
+
+impl Default for LookupOptions {
+    fn default() -> LookupOptions {
+        LookupOptions {
+            bias: Bias::GreatestLowerBound,
+            same_line_only: false,
+            max_column_distance: None,
+        }
+    }
+}
+
+impl LookupOptions {
+    /// Checks if a token at `token_pos` may be returned for a lookup at
+    /// `pos`.
+    fn accepts(&self, pos: (u32, u32), token_pos: (u32, u32), is_range: bool) -> bool {
+        if token_pos.0 != pos.0 {
+            return !self.same_line_only && self.max_column_distance.is_none();
+        }
+        match self.max_column_distance {
+            Some(max) => (is_range && token_pos.1 <= pos.1) || token_pos.1.abs_diff(pos.1) <= max,
+            None => true,
         }
     }
 }
@@ -86,6 +157,22 @@ impl DecodedMap {
         }
     }
 
+    /// Writes a decoded sourcemap to a writer with the given options.
+    pub fn to_writer_with_options<W: Write>(&self, w: W, options: &EncodeOptions) -> Result<()> {
+        match *self {
+            DecodedMap::Regular(ref sm) => encode_with_options(sm, w, options),
+            DecodedMap::Index(ref smi) => encode_with_options(smi, w, options),
+            DecodedMap::Hermes(ref smh) => encode_with_options(smh, w, options),
+        }
+    }
+
+    /// Checks the sourcemap for problems that the decoder accepts.
+    ///
+    /// See [`SourceMap::validate`] and [`SourceMapIndex::validate`].
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate_decoded(self)
+    }
+
     /// Shortcut to look up a token on either an index or a
     /// regular sourcemap.  This method can only be used if
     /// the contained index actually contains embedded maps
@@ -98,11 +185,40 @@ impl DecodedMap {
         }
     }
 
+    /// Shortcut to look up a token on either an index or a regular
+    /// sourcemap with the given options.
+    ///
+    /// See [`SourceMap::lookup_token_with_options`] for details.
+    pub fn lookup_token_with_options(
+        &self,
+        line: u32,
+        col: u32,
+        options: &LookupOptions,
+    ) -> Option<Token<'_>> {
+        match *self {
+            DecodedMap::Regular(ref sm) => sm.lookup_token_with_options(line, col, options),
+            DecodedMap::Index(ref smi) => smi.lookup_token_with_options(line, col, options),
+            DecodedMap::Hermes(ref smh) => smh.lookup_token_with_options(line, col, options),
+        }
+    }
+
+    /// Shortcut to look up the original frames on either an index or a
+    /// regular sourcemap.
+    ///
+    /// See [`SourceMap::lookup_frames`] for details.
+    pub fn lookup_frames(&self, line: u32, col: u32) -> Vec<OriginalFrame<'_>> {
+        match *self {
+            DecodedMap::Regular(ref sm) => sm.lookup_frames(line, col),
+            DecodedMap::Index(ref smi) => smi.lookup_frames(line, col),
+            DecodedMap::Hermes(ref smh) => smh.lookup_frames(line, col),
+        }
+    }
+
     /// Returns the original function name.
     ///
     /// `minified_name` and `source_view` are not always necessary.  For
-    /// instance hermes source maps can provide this information without
-    /// access to the original sources.
+    /// instance hermes source maps and sourcemaps with scope information
+    /// can provide this information without access to the original sources.
     pub fn get_original_function_name(
         &self,
         line: u32,
@@ -112,9 +228,15 @@ impl DecodedMap {
     ) -> Option<&str> {
         match *self {
             DecodedMap::Regular(ref sm) => {
+                if let Some(name) = sm.get_scope_function_name(line, col) {
+                    return Some(name);
+                }
                 sm.get_original_function_name(line, col, minified_name?, source_view?)
             }
             DecodedMap::Index(ref smi) => {
+                if let Some(name) = smi.lookup_frames(line, col).last().and_then(|f| f.name) {
+                    return Some(name);
+                }
                 smi.get_original_function_name(line, col, minified_name?, source_view?)
             }
             DecodedMap::Hermes(ref smh) => {
@@ -172,38 +294,126 @@ pub struct RawToken {
     pub is_range: bool,
 }
 
+/// The storage a [`Token`] resolves its sources, names and source views
+/// from.
+///
+/// This is implemented by [`SourceMap`] and
+/// [`BorrowedSourceMap`](crate::BorrowedSourceMap) so that both hand out
+/// the same token type.
+pub trait TokenStore {
+    /// Returns the tokens sorted by their generated position.
+    fn raw_tokens(&self) -> &[RawToken];
+
+    /// Looks up a source for a specific index.
+    fn get_source(&self, idx: u32) -> Option<&str>;
+
+    /// Looks up a name for a specific index.
+    fn get_name(&self, idx: u32) -> Option<&str>;
+
+    /// Returns the contents of a source as source view.
+    fn get_source_view(&self, idx: u32) -> Option<&SourceView>;
+}
+
+impl TokenStore for SourceMap {
+    fn raw_tokens(&self) -> &[RawToken] {
+        &self.tokens
+    }
+
+    fn get_source(&self, idx: u32) -> Option<&str> {
+        SourceMap::get_source(self, idx)
+    }
+
+    fn get_name(&self, idx: u32) -> Option<&str> {
+        SourceMap::get_name(self, idx)
+    }
+
+    fn get_source_view(&self, idx: u32) -> Option<&SourceView> {
+        SourceMap::get_source_view(self, idx)
+    }
+}
+
 /// Represents a token from a sourcemap
-#[derive(Copy, Clone)]
-pub struct Token<'a> {
+///
+/// Tokens of a [`SourceMap`] and of a
+/// [`BorrowedSourceMap`](crate::BorrowedSourceMap) share this type and only
+/// differ in the map they are linked to.
+pub struct Token<'a, M = SourceMap> {
     raw: &'a RawToken,
-    pub(crate) sm: &'a SourceMap,
+    pub(crate) sm: &'a M,
     pub(crate) idx: usize,
     offset: u32,
 }
 
-impl<'a> Token<'a> {
+impl<M> Clone for Token<'_, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for Token<'_, M> {}
+
+impl<'a, M: TokenStore> Token<'a, M> {
     /// The sourcemap this token is linked to.
-    pub fn sourcemap(&self) -> &'a SourceMap {
+    pub fn sourcemap(&self) -> &'a M {
         self.sm
     }
 }
 
-impl PartialEq for Token<'_> {
-    fn eq(&self, other: &Token<'_>) -> bool {
+/// Looks up a token of a sourcemap by its index.
+pub(crate) fn get_token<M: TokenStore>(sm: &M, idx: usize) -> Option<Token<'_, M>> {
+    sm.raw_tokens().get(idx).map(|raw| Token {
+        raw,
+        sm,
+        idx,
+        offset: 0,
+    })
+}
+
+/// Looks up the closest token of a sourcemap to a given position.
+///
+/// See [`SourceMap::lookup_token_with_options`].
+pub(crate) fn lookup_token<'a, M: TokenStore>(
+    sm: &'a M,
+    line: u32,
+    col: u32,
+    options: &LookupOptions,
+) -> Option<Token<'a, M>> {
+    let tokens = sm.raw_tokens();
+    let idx = match options.bias {
+        Bias::GreatestLowerBound => {
+            greatest_lower_bound(tokens, &(line, col), |t| (t.dst_line, t.dst_col))?.0
+        }
+        Bias::LeastUpperBound => tokens.partition_point(|t| (t.dst_line, t.dst_col) < (line, col)),
+    };
+    let mut token = get_token(sm, idx)?;
+
+    let is_range = token.is_range() && options.bias == Bias::GreatestLowerBound;
+    if !options.accepts((line, col), token.get_dst(), is_range) {
+        return None;
+    }
+    if is_range && token.get_dst_line() == line {
+        token.offset = col - token.get_dst_col();
+    }
+
+    Some(token)
+}
+
+impl<M> PartialEq for Token<'_, M> {
+    fn eq(&self, other: &Token<'_, M>) -> bool {
         self.raw == other.raw
     }
 }
 
-impl Eq for Token<'_> {}
+impl<M> Eq for Token<'_, M> {}
 
-impl PartialOrd for Token<'_> {
-    fn partial_cmp(&self, other: &Token<'_>) -> Option<Ordering> {
+impl<M: TokenStore> PartialOrd for Token<'_, M> {
+    fn partial_cmp(&self, other: &Token<'_, M>) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Token<'_> {
-    fn cmp(&self, other: &Token<'_>) -> Ordering {
+impl<M: TokenStore> Ord for Token<'_, M> {
+    fn cmp(&self, other: &Token<'_, M>) -> Ordering {
         macro_rules! try_cmp {
             ($a:expr, $b:expr) => {
                 match $a.cmp(&$b) {
@@ -226,7 +436,7 @@ impl Ord for Token<'_> {
     }
 }
 
-impl<'a> Token<'a> {
+impl<'a, M: TokenStore> Token<'a, M> {
     /// get the destination (minified) line number
     pub fn get_dst_line(&self) -> u32 {
         self.raw.dst_line
@@ -242,6 +452,38 @@ impl<'a> Token<'a> {
         (self.get_dst_line(), self.get_dst_col())
     }
 
+    /// Get the destination line and column where the token ends.
+    ///
+    /// A token extends up to the next token on the same line.  The last
+    /// token of a line extends to the end of the line, which is only known
+    /// if the generated file is passed as `generated`.  Columns are counted
+    /// in UTF-16 code units.
+    ///
+    /// ```rust
+    /// use sourcemap::{SourceMapBuilder, SourceView};
+    /// let mut builder = SourceMapBuilder::new(None);
+    /// builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+    /// builder.add(0, 4, 0, 0, Some("b.js"), None, false);
+    /// let sm = builder.into_sourcemap();
+    /// let generated = SourceView::from_string("a();b();".into());
+    ///
+    /// let token = sm.get_token(0).unwrap();
+    /// assert_eq!(token.get_dst_end(None), Some((0, 4)));
+    /// let token = sm.get_token(1).unwrap();
+    /// assert_eq!(token.get_dst_end(None), None);
+    /// assert_eq!(token.get_dst_end(Some(&generated)), Some((0, 8)));
+    /// ```
+    pub fn get_dst_end(&self, generated: Option<&SourceView>) -> Option<(u32, u32)> {
+        let line = self.get_dst_line();
+        match self.sm.raw_tokens().get(self.idx + 1) {
+            Some(next) if next.dst_line == line => Some((line, next.dst_col)),
+            _ => {
+                let len = generated?.get_line(line)?.encode_utf16().count() as u32;
+                Some((line, len.max(self.get_dst_col())))
+            }
+        }
+    }
+
     /// Get the source line number.
     ///
     /// `u32::MAX` is a sentinel value meaning
@@ -317,8 +559,13 @@ impl<'a> Token<'a> {
         *self.raw
     }
 
+    /// Get the index of the token in its sourcemap
+    pub fn get_idx(&self) -> usize {
+        self.idx
+    }
+
     /// Returns the referenced source view.
-    pub fn get_source_view(&self) -> Option<&SourceView> {
+    pub fn get_source_view(&self) -> Option<&'a SourceView> {
         self.sm.get_source_view(self.get_src_id())
     }
 
@@ -330,6 +577,25 @@ impl<'a> Token<'a> {
     }
 }
 
+/// A `(start, end)` pair of `(line, column)` positions.
+type Span = ((u32, u32), (u32, u32));
+
+/// The spans of an original source that are covered by a range of the
+/// generated file.
+///
+/// Returned by [`SourceMap::lookup_range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalSpans<'a> {
+    /// the source ID
+    pub src_id: u32,
+    /// the source as returned by [`SourceMap::get_source`]
+    pub source: &'a str,
+    /// sorted, non overlapping `(start, end)` positions in the original
+    /// source.  Both ends are inclusive.  A span is a single position unless
+    /// it comes from a range mapping.
+    pub spans: Vec<((u32, u32), (u32, u32))>,
+}
+
 /// Iterates over all tokens in a sourcemap
 pub struct TokenIter<'a> {
     i: &'a SourceMap,
@@ -359,6 +625,40 @@ impl<'a> Iterator for TokenIter<'a> {
     }
 }
 
+/// The part of the generated file that a token covers.
+///
+/// Returned by [`SourceMap::generated_spans`].
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratedSpan<'a> {
+    /// the token
+    pub token: Token<'a>,
+    /// the destination line and column the token starts at
+    pub start: (u32, u32),
+    /// the destination line and column the token ends at (exclusive) if
+    /// known.  See [`Token::get_dst_end`].
+    pub end: Option<(u32, u32)>,
+}
+
+/// Iterates over the generated spans of all tokens in a sourcemap
+pub struct GeneratedSpanIter<'a> {
+    tokens: TokenIter<'a>,
+    generated: Option<&'a SourceView>,
+}
+
+impl<'a> Iterator for GeneratedSpanIter<'a> {
+    type Item = GeneratedSpan<'a>;
+
+    fn next(&mut self) -> Option<GeneratedSpan<'a>> {
+        let token = self.tokens.next()?;
+        let end = token.get_dst_end(self.generated);
+        Some(GeneratedSpan {
+            token,
+            start: token.get_dst(),
+            end,
+        })
+    }
+}
+
 /// Iterates over all sources in a sourcemap
 pub struct SourceIter<'a> {
     i: &'a SourceMap,
@@ -411,13 +711,13 @@ impl<'a> Iterator for NameIter<'a> {
     }
 }
 
-impl fmt::Debug for Token<'_> {
+impl<M: TokenStore> fmt::Debug for Token<'_, M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "<Token {self:#}>")
     }
 }
 
-impl fmt::Display for Token<'_> {
+impl<M: TokenStore> fmt::Display for Token<'_, M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -474,6 +774,7 @@ pub struct SourceMapIndex {
     x_facebook_offsets: Option<Vec<Option<u32>>>,
     x_metro_module_paths: Option<Vec<String>>,
     debug_id: Option<DebugId>,
+    pub(crate) extensions: Extensions,
 }
 
 /// Represents a sourcemap in memory
@@ -490,8 +791,30 @@ pub struct SourceMap {
     pub(crate) sources: Vec<Arc<str>>,
     pub(crate) sources_prefixed: Option<Vec<Arc<str>>>,
     pub(crate) sources_content: Vec<Option<SourceView>>,
+    pub(crate) original_scopes: Vec<Option<OriginalScope>>,
+    pub(crate) generated_ranges: Vec<GeneratedRange>,
     pub(crate) ignore_list: BTreeSet<u32>,
     pub(crate) debug_id: Option<DebugId>,
+    pub(crate) extensions: Extensions,
+    pub(crate) reverse_index: ReverseIndex,
+}
+
+/// Token indexes sorted by original position, built on first use.
+///
+/// The index is derived from the tokens so it is ignored for comparisons.
+#[derive(Clone, Default)]
+pub(crate) struct ReverseIndex(OnceLock<Vec<u32>>);
+
+impl PartialEq for ReverseIndex {
+    fn eq(&self, _other: &ReverseIndex) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for ReverseIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReverseIndex")
+    }
 }
 
 impl SourceMap {
@@ -546,6 +869,112 @@ impl SourceMap {
         encode(self, w)
     }
 
+    /// Writes a sourcemap into a writer with the given options.
+    ///
+    /// ```rust
+    /// use sourcemap::{EncodeOptions, IgnoreListKey, SourceMap};
+    /// let input: &[_] = br#"{
+    ///     "version": 3,
+    ///     "sources": ["node_modules/lib.js"],
+    ///     "names": [],
+    ///     "mappings": "AAAA",
+    ///     "ignoreList": [0]
+    /// }"#;
+    /// let sm = SourceMap::from_slice(input).unwrap();
+    /// let options = EncodeOptions {
+    ///     ignore_list_key: IgnoreListKey::Both,
+    /// };
+    /// let mut output: Vec<u8> = vec![];
+    /// sm.to_writer_with_options(&mut output, &options).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    /// assert!(output.contains(r#""ignoreList":[0],"x_google_ignoreList":[0]"#));
+    /// ```
+    pub fn to_writer_with_options<W: Write>(&self, w: W, options: &EncodeOptions) -> Result<()> {
+        encode_with_options(self, w, options)
+    }
+
+    /// Writes a sourcemap into a writer in a compact binary format.
+    ///
+    /// The binary format is meant for caching decoded sourcemaps: loading
+    /// it with [`SourceMap::from_binary_slice`] requires no JSON parsing or
+    /// VLQ decoding.  Scope information is not preserved.
+    ///
+    /// ```rust
+    /// # use sourcemap::SourceMap;
+    /// # let input: &[_] = b"{
+    /// #     \"version\":3,
+    /// #     \"sources\":[\"coolstuff.js\"],
+    /// #     \"names\":[\"x\",\"alert\"],
+    /// #     \"mappings\":\"AAAA,GAAIA,GAAI,EACR,IAAIA,GAAK,EAAG,CACVC,MAAM\"
+    /// # }";
+    /// let sm = SourceMap::from_reader(input).unwrap();
+    /// let mut output : Vec<u8> = vec![];
+    /// sm.to_binary_writer(&mut output).unwrap();
+    /// assert_eq!(SourceMap::from_binary_slice(&output).unwrap(), sm);
+    /// ```
+    pub fn to_binary_writer<W: Write>(&self, w: W) -> Result<()> {
+        encode_binary(self, w)
+    }
+
+    /// Loads a sourcemap from a slice written by
+    /// [`SourceMap::to_binary_writer`].
+    ///
+    /// This copies all tokens and strings.  Use
+    /// [`BinarySourceMap`](crate::BinarySourceMap) to look up tokens
+    /// directly in the slice, for instance in a memory mapped file.
+    pub fn from_binary_slice(slice: &[u8]) -> Result<SourceMap> {
+        decode_binary(slice)
+    }
+
+    /// Computes quality metrics of the sourcemap for the given generated
+    /// file.
+    ///
+    /// ```rust
+    /// use sourcemap::{SourceMapBuilder, SourceView};
+    /// let mut builder = SourceMapBuilder::new(None);
+    /// builder.add(0, 0, 0, 0, Some("a.js"), Some("a"), false);
+    /// builder.add(0, 4, 0, 0, None, None, false);
+    /// let sm = builder.into_sourcemap();
+    ///
+    /// let stats = sm.stats(&SourceView::new("a();b();".into()));
+    /// assert_eq!(stats.mapped_columns_percentage(), 50.0);
+    /// assert_eq!(stats.tokens_without_source, 1);
+    /// assert_eq!(stats.sources_without_content, vec!["a.js"]);
+    /// ```
+    pub fn stats(&self, generated: &SourceView) -> SourceMapStats {
+        sourcemap_stats(self, generated)
+    }
+
+    /// Checks the sourcemap for problems that the decoder accepts.
+    ///
+    /// This reports tokens pointing outside of their `sourcesContent`,
+    /// names that do not match the source text at their position, duplicate
+    /// mappings, out of range `ignoreList` entries and a `sourcesContent`
+    /// that does not match the `sources`.  Checks that need the source
+    /// contents are skipped for sources without contents.
+    ///
+    /// Unsorted mappings can only be detected before decoding, see
+    /// [`decode_strict`](crate::decode_strict).
+    ///
+    /// ```rust
+    /// use sourcemap::{DiagnosticKind, SourceMap};
+    /// let input: &[_] = br#"{
+    ///     "version": 3,
+    ///     "sources": ["foo.js"],
+    ///     "sourcesContent": ["var x;"],
+    ///     "names": ["y"],
+    ///     "mappings": "AAAA,IAAIA"
+    /// }"#;
+    /// let sm = SourceMap::from_slice(input).unwrap();
+    /// let diagnostics = sm.validate();
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(diagnostics[0].position, Some((0, 4)));
+    /// assert!(matches!(diagnostics[0].kind, DiagnosticKind::NameMismatch { .. }));
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate_sourcemap(self)
+    }
+
     /// Encode a sourcemap into a data url.
     ///
     /// ```rust
@@ -616,8 +1045,12 @@ impl SourceMap {
                 .into_iter()
                 .map(|opt| opt.map(SourceView::new))
                 .collect(),
+            original_scopes: vec![],
+            generated_ranges: vec![],
             ignore_list: BTreeSet::default(),
             debug_id: None,
+            extensions: Extensions::default(),
+            reverse_index: ReverseIndex::default(),
         }
     }
 
@@ -631,6 +1064,16 @@ impl SourceMap {
         self.debug_id = debug_id
     }
 
+    /// Returns the extension fields of the sourcemap.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns the extension fields of the sourcemap for modification.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     /// Returns the embedded filename in case there is one.
     pub fn get_file(&self) -> Option<&str> {
         self.file.as_deref()
@@ -646,7 +1089,7 @@ impl SourceMap {
         self.source_root.as_deref()
     }
 
-    fn prefix_source(source_root: &str, source: &str) -> Arc<str> {
+    pub(crate) fn prefix_source(source_root: &str, source: &str) -> Arc<str> {
         let source_root = source_root.strip_suffix('/').unwrap_or(source_root);
         let is_valid = !source.is_empty()
             && (source.starts_with('/')
@@ -687,12 +1130,7 @@ impl SourceMap {
 
     /// Looks up a token by its index.
     pub fn get_token(&self, idx: usize) -> Option<Token<'_>> {
-        self.tokens.get(idx).map(|raw| Token {
-            raw,
-            sm: self,
-            idx,
-            offset: 0,
-        })
+        get_token(self, idx)
     }
 
     /// Returns the number of tokens in the sourcemap.
@@ -708,33 +1146,358 @@ impl SourceMap {
         }
     }
 
+    /// Returns an iterator over the generated spans of all tokens.
+    ///
+    /// See [`Token::get_dst_end`] for how the end of a span is determined.
+    pub fn generated_spans<'a>(
+        &'a self,
+        generated: Option<&'a SourceView>,
+    ) -> GeneratedSpanIter<'a> {
+        GeneratedSpanIter {
+            tokens: self.tokens(),
+            generated,
+        }
+    }
+
     /// Looks up the closest token to a given 0-indexed line and column.
+    ///
+    /// This returns the closest token before the position, even if it is on
+    /// a previous line.  Use
+    /// [`lookup_token_with_options`](Self::lookup_token_with_options) to
+    /// restrict the lookup.
     pub fn lookup_token(&self, line: u32, col: u32) -> Option<Token<'_>> {
-        let (idx, raw) =
-            greatest_lower_bound(&self.tokens, &(line, col), |t| (t.dst_line, t.dst_col))?;
-
-        let mut token = Token {
-            raw,
-            sm: self,
-            idx,
-            offset: 0,
+        self.lookup_token_with_options(line, col, &LookupOptions::default())
+    }
+
+    /// Looks up the closest token to a given 0-indexed line and column with
+    /// the given options.
+    ///
+    /// `None` is returned if the closest token in the direction of the
+    /// [`Bias`] does not satisfy the options.
+    ///
+    /// ```rust
+    /// use sourcemap::{Bias, LookupOptions, SourceMapBuilder};
+    /// let mut builder = SourceMapBuilder::new(None);
+    /// builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+    /// builder.add(0, 20, 5, 0, Some("a.js"), None, false);
+    /// let sm = builder.into_sourcemap();
+    ///
+    /// let options = LookupOptions {
+    ///     max_column_distance: Some(5),
+    ///     ..Default::default()
+    /// };
+    /// assert!(sm.lookup_token_with_options(0, 10, &options).is_none());
+    /// assert!(sm.lookup_token_with_options(1, 0, &options).is_none());
+    ///
+    /// let options = LookupOptions {
+    ///     bias: Bias::LeastUpperBound,
+    ///     ..Default::default()
+    /// };
+    /// let token = sm.lookup_token_with_options(0, 10, &options).unwrap();
+    /// assert_eq!(token.get_src_line(), 5);
+    /// ```
+    pub fn lookup_token_with_options(
+        &self,
+        line: u32,
+        col: u32,
+        options: &LookupOptions,
+    ) -> Option<Token<'_>> {
+        lookup_token(self, line, col, options)
+    }
+
+    fn get_reverse_index(&self) -> &[u32] {
+        self.reverse_index.0.get_or_init(|| {
+            let mut index: Vec<u32> = (0..self.tokens.len() as u32)
+                .filter(|&idx| self.tokens[idx as usize].src_id != !0)
+                .collect();
+            index.sort_unstable_by_key(|&idx| {
+                let t = &self.tokens[idx as usize];
+                (t.src_id, t.src_line, t.src_col, t.dst_line, t.dst_col)
+            });
+            index
+        })
+    }
+
+    fn src_ids_for_source<'b>(&'b self, source: &'b str) -> impl Iterator<Item = u32> + 'b {
+        (0..self.get_source_count()).filter(move |&idx| self.get_source(idx) == Some(source))
+    }
+
+    /// Looks up the tokens that map to a given 0-indexed line and column
+    /// in an original source.
+    ///
+    /// This returns all tokens for the closest original position on the
+    /// same line at or before the given column, ordered by their position
+    /// in the generated file.  The `source` is matched against the values
+    /// returned by [`SourceMap::get_source`].  An index of the tokens is
+    /// built on first use.
+    ///
+    /// ```rust
+    /// # use sourcemap::SourceMap;
+    /// let input: &[_] = b"{
+    ///     \"version\":3,
+    ///     \"sources\":[\"coolstuff.js\"],
+    ///     \"names\":[\"x\",\"alert\"],
+    ///     \"mappings\":\"AAAA,GAAIA,GAAI,EACR,IAAIA,GAAK,EAAG,CACVC,MAAM\"
+    /// }";
+    /// let sm = SourceMap::from_reader(input).unwrap();
+    /// let tokens = sm.lookup_generated("coolstuff.js", 0, 5);
+    /// assert_eq!(tokens.len(), 1);
+    /// assert_eq!(tokens[0].get_dst(), (0, 3));
+    /// ```
+    pub fn lookup_generated(&self, source: &str, line: u32, col: u32) -> Vec<Token<'_>> {
+        let index = self.get_reverse_index();
+        let key = |idx: &u32| {
+            let t = &self.tokens[*idx as usize];
+            (t.src_id, t.src_line, t.src_col)
+        };
+
+        let mut rv = vec![];
+        for src_id in self.src_ids_for_source(source) {
+            let end = index.partition_point(|idx| key(idx) <= (src_id, line, col));
+            let closest = match end.checked_sub(1).map(|idx| key(&index[idx])) {
+                Some(closest) if closest.0 == src_id && closest.1 == line => closest,
+                _ => continue,
+            };
+            let start = index[..end].partition_point(|idx| key(idx) < closest);
+            rv.extend(
+                index[start..end]
+                    .iter()
+                    .filter_map(|&idx| self.get_token(idx as usize)),
+            );
+        }
+        rv.sort_by_key(Token::get_dst);
+        rv
+    }
+
+    /// Looks up all tokens that map into a range of an original source.
+    ///
+    /// The range is given as 0-indexed `(line, column)` pairs, where the
+    /// start is inclusive and the end exclusive.  Tokens are ordered by
+    /// their original position.  For instance all tokens for line `10` are
+    /// returned by `sm.lookup_generated_range(source, (10, 0), (11, 0))`.
+    pub fn lookup_generated_range(
+        &self,
+        source: &str,
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> Vec<Token<'_>> {
+        let index = self.get_reverse_index();
+        let key = |idx: &u32| {
+            let t = &self.tokens[*idx as usize];
+            (t.src_id, t.src_line, t.src_col)
         };
 
-        if token.is_range() {
-            token.offset = col - token.get_dst_col();
+        let mut rv = vec![];
+        for src_id in self.src_ids_for_source(source) {
+            let lo = index.partition_point(|idx| key(idx) < (src_id, start.0, start.1));
+            let hi = index.partition_point(|idx| key(idx) < (src_id, end.0, end.1));
+            rv.extend(
+                index[lo..hi.max(lo)]
+                    .iter()
+                    .filter_map(|&idx| self.get_token(idx as usize)),
+            );
+        }
+        rv
+    }
+
+    /// Maps a range of the generated file to the spans of the original
+    /// sources it covers.
+    ///
+    /// The range is given as 0-indexed `(line, column)` pairs, where the
+    /// start is inclusive and the end exclusive.  Every token in the range
+    /// contributes the original positions it maps to: a regular token only
+    /// maps a single position while a range mapping covers every column up
+    /// to the next token or the end of the range.  Overlapping spans are
+    /// merged, but nothing between the mapped positions is filled in.  The
+    /// result has one entry per source in the order the sources are first
+    /// encountered.
+    ///
+    /// ```rust
+    /// # use sourcemap::SourceMapBuilder;
+    /// let mut builder = SourceMapBuilder::new(None);
+    /// builder.add(0, 0, 3, 0, Some("a.js"), None, false);
+    /// builder.add(0, 5, 3, 8, Some("a.js"), None, false);
+    /// builder.add(0, 9, 0, 0, Some("b.js"), None, false);
+    /// let sm = builder.into_sourcemap();
+    ///
+    /// let spans = sm.lookup_range((0, 2), (0, 10));
+    /// assert_eq!(spans.len(), 2);
+    /// assert_eq!(spans[0].source, "a.js");
+    /// assert_eq!(spans[0].spans, vec![((3, 0), (3, 0)), ((3, 8), (3, 8))]);
+    /// ```
+    pub fn lookup_range(&self, start: (u32, u32), end: (u32, u32)) -> Vec<OriginalSpans<'_>> {
+        let mut idx = self
+            .tokens
+            .partition_point(|t| (t.dst_line, t.dst_col) <= start);
+        // the token before the range covers its start if it is on the same line
+        if idx > 0 && self.tokens[idx - 1].dst_line == start.0 {
+            idx -= 1;
+        }
+
+        let mut rv: Vec<OriginalSpans<'_>> = vec![];
+        let mut push_span = |src_id: u32, span: Span| {
+            let spans = match rv.iter_mut().find(|spans| spans.src_id == src_id) {
+                Some(spans) => spans,
+                None => {
+                    rv.push(OriginalSpans {
+                        src_id,
+                        source: self.get_source(src_id).unwrap_or_default(),
+                        spans: vec![],
+                    });
+                    rv.last_mut().unwrap()
+                }
+            };
+            spans.spans.push(span);
+        };
+
+        for (token_idx, token) in self.tokens.iter().enumerate().skip(idx) {
+            let dst = (token.dst_line, token.dst_col);
+            if dst >= end {
+                break;
+            }
+            if token.src_id == !0 {
+                continue;
+            }
+
+            let mut first = (token.src_line, token.src_col);
+            let mut last = first;
+            if token.is_range {
+                if dst < start {
+                    first.1 += start.1 - token.dst_col;
+                }
+                let next_col = self
+                    .tokens
+                    .get(token_idx + 1)
+                    .filter(|next| next.dst_line == token.dst_line)
+                    .map_or(u32::MAX, |next| next.dst_col);
+                let end_col = if end.0 == token.dst_line {
+                    next_col.min(end.1)
+                } else {
+                    next_col
+                };
+                // a range token followed by a token at the same column is
+                // zero-width and only covers its own position
+                last = if end_col == u32::MAX || end_col <= token.dst_col {
+                    first
+                } else {
+                    (
+                        token.src_line,
+                        token.src_col + (end_col - 1 - token.dst_col),
+                    )
+                };
+            }
+
+            push_span(token.src_id, (first, last));
+        }
+
+        for spans in &mut rv {
+            spans.spans.sort_unstable();
+            let mut merged: Vec<Span> = vec![];
+            for (span_start, span_end) in spans.spans.drain(..) {
+                match merged.last_mut() {
+                    Some(prev) if span_start <= prev.1 => prev.1 = prev.1.max(span_end),
+                    _ => merged.push((span_start, span_end)),
+                }
+            }
+            spans.spans = merged;
+        }
+
+        rv
+    }
+
+    /// Returns the ranges in the generated file that cover a given 0-indexed
+    /// line and column, ordered from outermost to innermost.
+    pub fn lookup_generated_ranges(&self, line: u32, col: u32) -> Vec<&GeneratedRange> {
+        let mut rv = vec![];
+        let mut ranges = &self.generated_ranges[..];
+        while let Some((_, range)) = greatest_lower_bound(ranges, &(line, col), |r| r.start) {
+            if !range.contains(line, col) {
+                break;
+            }
+            rv.push(range);
+            ranges = &range.children;
+        }
+        rv
+    }
+
+    /// Looks up the original frames for a given 0-indexed line and column.
+    ///
+    /// The frames are ordered from outermost to innermost.  If the sourcemap
+    /// carries scope information, functions that were inlined at the given
+    /// position are expanded into frames of their own and frames are named
+    /// after their original function.  Ranges that are marked as hidden do
+    /// not produce frames.  Without scope information at most a single
+    /// unnamed frame for the closest token is returned.
+    pub fn lookup_frames(&self, line: u32, col: u32) -> Vec<OriginalFrame<'_>> {
+        let token = match self.lookup_token(line, col) {
+            Some(token) if token.has_source() => token,
+            _ => return vec![],
+        };
+
+        let make_frame = |(src_id, src_line, src_col), name| OriginalFrame {
+            src_id,
+            source: self.get_source(src_id),
+            src_line,
+            src_col,
+            name,
+        };
+
+        let mut frames = vec![];
+        let mut pos = (
+            token.get_src_id(),
+            token.get_src_line(),
+            token.get_src_col(),
+        );
+        let mut reached_outermost = false;
+
+        for range in self.lookup_generated_ranges(line, col).into_iter().rev() {
+            if range.callsite.is_none() && !range.is_stack_frame {
+                continue;
+            }
+
+            if !range.is_hidden {
+                let name = range
+                    .definition
+                    .and_then(|def| self.get_scope_definition(def))
+                    .and_then(OriginalScope::get_name);
+                frames.push(make_frame(pos, name));
+            }
+
+            match range.callsite {
+                Some(callsite) => pos = (callsite.src_id, callsite.src_line, callsite.src_col),
+                None => {
+                    reached_outermost = true;
+                    break;
+                }
+            }
+        }
+
+        if !reached_outermost {
+            frames.push(make_frame(pos, None));
         }
 
-        Some(token)
+        frames.reverse();
+        frames
+    }
+
+    /// Returns the name of the original function at a given 0-indexed line
+    /// and column based on scope information.
+    fn get_scope_function_name(&self, line: u32, col: u32) -> Option<&str> {
+        if !self.has_scopes() {
+            return None;
+        }
+        self.lookup_frames(line, col).last()?.name
     }
 
     /// Given a location, name and minified source file resolve a minified
     /// name to an original function name.
     ///
-    /// This invokes some guesswork and requires access to the original minified
-    /// source.  This will not yield proper results for anonymous functions or
-    /// functions that do not have clear function names.  (For instance it's
-    /// recommended that dotted function names are not passed to this
-    /// function).
+    /// If the sourcemap carries scope information the name is taken from
+    /// there.  Otherwise this invokes some guesswork and requires access to
+    /// the original minified source.  This will not yield proper results for
+    /// anonymous functions or functions that do not have clear function
+    /// names.  (For instance it's recommended that dotted function names are
+    /// not passed to this function).
     pub fn get_original_function_name(
         &self,
         line: u32,
@@ -742,6 +1505,9 @@ impl SourceMap {
         minified_name: &str,
         sv: &SourceView,
     ) -> Option<&str> {
+        if let Some(name) = self.get_scope_function_name(line, col) {
+            return Some(name);
+        }
         self.lookup_token(line, col)
             .and_then(|token| sv.get_original_function_name(token, minified_name))
     }
@@ -810,6 +1576,49 @@ impl SourceMap {
         }
     }
 
+    /// Returns the original scope tree for a source.
+    ///
+    /// This is only available if the sourcemap carries scope information.
+    pub fn get_original_scope(&self, idx: u32) -> Option<&OriginalScope> {
+        self.original_scopes
+            .get(idx as usize)
+            .and_then(Option::as_ref)
+    }
+
+    /// Sets the original scope tree for a source.
+    ///
+    /// This panics if a scope is set for a source that does not exist.
+    pub fn set_original_scope(&mut self, idx: u32, scope: Option<OriginalScope>) {
+        if self.original_scopes.len() != self.sources.len() {
+            self.original_scopes.resize(self.sources.len(), None);
+        }
+        self.original_scopes[idx as usize] = scope;
+    }
+
+    /// Resolves a reference to an original scope.
+    pub fn get_scope_definition(&self, scope_ref: OriginalScopeRef) -> Option<&OriginalScope> {
+        self.get_original_scope(scope_ref.src_id)?
+            .get_scope_by_index(scope_ref.scope_idx)
+    }
+
+    /// Returns the top-level ranges in the generated file.
+    pub fn generated_ranges(&self) -> &[GeneratedRange] {
+        &self.generated_ranges
+    }
+
+    /// Replaces the ranges in the generated file.
+    ///
+    /// The ranges and their children are sorted by their start position.
+    pub fn set_generated_ranges(&mut self, mut ranges: Vec<GeneratedRange>) {
+        sort_generated_ranges(&mut ranges);
+        self.generated_ranges = ranges;
+    }
+
+    /// Returns true if the sourcemap carries scope information.
+    pub fn has_scopes(&self) -> bool {
+        !self.generated_ranges.is_empty() || self.original_scopes.iter().any(Option::is_some)
+    }
+
     /// Returns an iterator over the names.
     pub fn names(&self) -> NameIter<'_> {
         NameIter {
@@ -865,7 +1674,7 @@ impl SourceMap {
 
     /// Same as `rewrite`, except also returns a remapping index for deduplicated `sources`.
     pub(crate) fn rewrite_with_mapping(
-        self,
+        mut self,
         options: &RewriteOptions<'_>,
     ) -> Result<(SourceMap, Vec<u32>)> {
         let mut builder = SourceMapBuilder::new(self.get_file());
@@ -880,6 +1689,13 @@ impl SourceMap {
                 builder
                     .set_source_contents(raw.src_id, self.get_source_contents(token.get_src_id()));
             }
+            if raw.src_id != !0 && self.ignore_list.contains(&token.get_src_id()) {
+                builder.add_to_ignore_list(raw.src_id);
+            }
+        }
+
+        if let Some(rules) = options.ignore_rules {
+            rules.apply_to_builder(&mut builder);
         }
 
         #[cfg(any(unix, windows, target_os = "redox"))]
@@ -909,7 +1725,26 @@ impl SourceMap {
 
         let mapping = builder.take_mapping();
 
-        let sm = builder.into_sourcemap();
+        if self.has_scopes() {
+            let mut new_ids = vec![None; self.sources.len()];
+            for (new_id, &old_id) in mapping.iter().enumerate() {
+                if let Some(slot) = new_ids.get_mut(old_id as usize) {
+                    *slot = Some(new_id as u32);
+                }
+            }
+            for (new_id, &old_id) in mapping.iter().enumerate() {
+                if let Some(scope) = self.original_scopes.get_mut(old_id as usize) {
+                    builder.set_original_scope(new_id as u32, scope.take());
+                }
+            }
+            for mut range in std::mem::take(&mut self.generated_ranges) {
+                range.remap_sources(&|src_id| new_ids.get(src_id as usize).copied().flatten());
+                builder.add_generated_range(range);
+            }
+        }
+
+        let mut sm = builder.into_sourcemap();
+        sm.extensions = std::mem::take(&mut self.extensions);
 
         Ok((sm, mapping))
     }
@@ -959,18 +1794,19 @@ impl SourceMap {
         // Helper struct that makes it easier to compare tokens by the start and end
         // of the range they cover.
         #[derive(Debug, Clone, Copy)]
-        struct Range<'a> {
+        struct Range {
             start: (u32, u32),
             end: (u32, u32),
-            value: &'a RawToken,
+            value: RawToken,
         }
 
-        /// Turns a list of tokens into a list of ranges, using the provided `key` function to determine the order of the tokens.
-        #[allow(clippy::ptr_arg)]
-        fn create_ranges(
-            tokens: &mut [RawToken],
-            key: fn(&RawToken) -> (u32, u32),
-        ) -> Vec<Range<'_>> {
+        /// Turns the `adjustment` tokens into a list of ranges in the original source file.
+        ///
+        /// A token extends either to the start of the next token or the end of the line,
+        /// whichever comes sooner.
+        fn create_source_ranges(adjustment: &SourceMap) -> Vec<Range> {
+            let key = |t: &RawToken| (t.src_line, t.src_col);
+            let mut tokens = adjustment.tokens.clone();
             tokens.sort_unstable_by_key(key);
 
             let mut token_iter = tokens.iter().peekable();
@@ -979,12 +1815,11 @@ impl SourceMap {
             while let Some(t) = token_iter.next() {
                 let start = key(t);
                 let next_start = token_iter.peek().map_or((u32::MAX, u32::MAX), |t| key(t));
-                // A token extends either to the start of the next token or the end of the line, whichever comes sooner
                 let end = std::cmp::min(next_start, (start.0, u32::MAX));
                 ranges.push(Range {
                     start,
                     end,
-                    value: t,
+                    value: *t,
                 });
             }
 
@@ -995,11 +1830,20 @@ impl SourceMap {
         // both start and end.
         // We want to compare `self` and `adjustment` tokens by line/column numbers in the "original source" file.
         // These line/column numbers are the `dst_line/col` for
-        // the `self` tokens and `src_line/col` for the `adjustment` tokens.
-        let mut self_tokens = std::mem::take(&mut self.tokens);
-        let original_ranges = create_ranges(&mut self_tokens, |t| (t.dst_line, t.dst_col));
-        let mut adjustment_tokens = adjustment.tokens.clone();
-        let adjustment_ranges = create_ranges(&mut adjustment_tokens, |t| (t.src_line, t.src_col));
+        // the `self` tokens, which are exactly their generated spans, and `src_line/col` for the
+        // `adjustment` tokens.
+        self.tokens
+            .sort_unstable_by_key(|t| (t.dst_line, t.dst_col));
+        let original_ranges: Vec<_> = self
+            .generated_spans(None)
+            .map(|span| Range {
+                start: span.start,
+                end: span.end.unwrap_or((span.start.0, u32::MAX)),
+                value: span.token.get_raw_token(),
+            })
+            .collect();
+        self.tokens.clear();
+        let adjustment_ranges = create_source_ranges(adjustment);
 
         let mut original_ranges_iter = original_ranges.iter();
 
@@ -1036,7 +1880,7 @@ impl SourceMap {
                 let mut token = RawToken {
                     dst_line,
                     dst_col,
-                    ..*original_range.value
+                    ..original_range.value
                 };
 
                 token.dst_line = (token.dst_line as i32 + line_diff) as u32;
@@ -1060,7 +1904,159 @@ impl SourceMap {
 
         self.tokens
             .sort_unstable_by_key(|t| (t.dst_line, t.dst_col));
+        self.reverse_index = ReverseIndex::default();
     }
+
+    /// Composes this sourcemap with the sourcemaps of earlier transformations.
+    ///
+    /// `upstream` is ordered from the most recent transformation to the
+    /// first one.  For instance if TypeScript is compiled with Babel and
+    /// minified with Terser, the Terser map is composed with
+    /// `&[&babel_map, &typescript_map]`.  Each upstream map describes the
+    /// file the sources of the map before it refer to.
+    ///
+    /// A token is traced back through an upstream map if its source refers
+    /// to the `file` of that map.  The paths match if they are equal or one
+    /// ends with the other at a `/`, ignoring a leading `./`.  Tokens of
+    /// other sources are passed through unchanged.  An upstream map without
+    /// a `file` applies to every token.
+    ///
+    /// Names of the upstream tokens take precedence, source contents and the
+    /// ignore list are taken from the upstream map, and range mappings stay
+    /// ranges as long as they are ranges in every map.  Tokens that cannot be
+    /// traced become unmapped.  Scope information is not carried over.
+    ///
+    /// ```rust
+    /// # use sourcemap::SourceMapBuilder;
+    /// let mut builder = SourceMapBuilder::new(Some("bundle.min.js"));
+    /// builder.add(0, 0, 2, 4, Some("bundle.js"), None, false);
+    /// let minified = builder.into_sourcemap();
+    ///
+    /// let mut builder = SourceMapBuilder::new(Some("bundle.js"));
+    /// builder.add(2, 0, 7, 0, Some("input.ts"), None, false);
+    /// let compiled = builder.into_sourcemap();
+    ///
+    /// let sm = minified.compose(&[&compiled]);
+    /// let token = sm.lookup_token(0, 0).unwrap();
+    /// assert_eq!(token.to_tuple(), ("input.ts", 7, 0, None));
+    /// ```
+    pub fn compose(&self, upstream: &[&SourceMap]) -> SourceMap {
+        let mut rv = Cow::Borrowed(self);
+        for &inner in upstream {
+            rv = Cow::Owned(rv.compose_with(inner));
+        }
+        rv.into_owned()
+    }
+
+    fn compose_with(&self, inner: &SourceMap) -> SourceMap {
+        let mut builder = SourceMapBuilder::new(self.get_file());
+        builder.set_debug_id(self.debug_id);
+
+        let add = |builder: &mut SourceMapBuilder,
+                   (dst_line, dst_col): (u32, u32),
+                   traced: Option<Token<'_>>,
+                   name: Option<&str>,
+                   is_range: bool| {
+            let traced = match traced.filter(Token::has_source) {
+                Some(traced) => traced,
+                None => {
+                    builder.add_raw(dst_line, dst_col, !0, !0, None, None, false);
+                    return;
+                }
+            };
+            let raw = builder.add(
+                dst_line,
+                dst_col,
+                traced.get_src_line(),
+                traced.get_src_col(),
+                traced.get_source(),
+                traced.get_name().or(name),
+                is_range && traced.is_range(),
+            );
+            if !builder.has_source_contents(raw.src_id) {
+                if let Some(contents) = traced.sm.get_source_contents(traced.get_src_id()) {
+                    builder.set_source_contents(raw.src_id, Some(contents));
+                }
+            }
+            if traced.sm.ignore_list.contains(&traced.get_src_id()) {
+                builder.add_to_ignore_list(raw.src_id);
+            }
+        };
+
+        for token in self.tokens() {
+            let dst = token.get_dst();
+            if !token.has_source() {
+                add(&mut builder, dst, None, None, false);
+                continue;
+            }
+            if !is_upstream_source(token.get_source().unwrap_or_default(), inner.get_file()) {
+                add(&mut builder, dst, Some(token), None, token.is_range());
+                continue;
+            }
+
+            let (src_line, src_col) = token.get_src();
+            let traced = inner
+                .lookup_token(src_line, src_col)
+                .filter(|t| t.get_dst_line() == src_line);
+            add(
+                &mut builder,
+                dst,
+                traced,
+                token.get_name(),
+                token.is_range(),
+            );
+
+            if !token.is_range() {
+                continue;
+            }
+
+            // A range token covers everything up to the next token on the
+            // same line.  Upstream tokens that start within that extent need
+            // tokens of their own.
+            let extent = match self.get_token(token.idx + 1) {
+                Some(next) if next.get_dst_line() == dst.0 => next.get_dst_col() - dst.1,
+                _ => u32::MAX,
+            };
+            let start_idx = inner
+                .tokens
+                .partition_point(|t| (t.dst_line, t.dst_col) <= (src_line, src_col));
+            for idx in start_idx..inner.tokens.len() {
+                let nested = inner.get_token(idx).unwrap();
+                if nested.get_dst_line() != src_line {
+                    break;
+                }
+                let delta = nested.get_dst_col() - src_col;
+                if delta >= extent {
+                    break;
+                }
+                add(
+                    &mut builder,
+                    (dst.0, dst.1 + delta),
+                    Some(nested),
+                    None,
+                    true,
+                );
+            }
+        }
+
+        let mut sm = builder.into_sourcemap();
+        sm.extensions = self.extensions.clone();
+        sm
+    }
+}
+
+/// Checks if a source refers to the file an upstream sourcemap describes.
+fn is_upstream_source(source: &str, file: Option<&str>) -> bool {
+    let file = match file {
+        Some(file) => file.trim_start_matches("./"),
+        None => return true,
+    };
+    let source = source.trim_start_matches("./");
+    let is_suffix = |path: &str, suffix: &str| {
+        path.strip_suffix(suffix)
+            .is_some_and(|prefix| prefix.ends_with('/'))
+    };
+    source == file || is_suffix(source, file) || is_suffix(file, source)
 }
 
 impl SourceMapIndex {
@@ -1080,6 +2076,13 @@ impl SourceMapIndex {
         encode(self, w)
     }
 
+    /// Writes a sourcemap index into a writer with the given options.
+    ///
+    /// The options also apply to the sourcemaps of the sections.
+    pub fn to_writer_with_options<W: Write>(&self, w: W, options: &EncodeOptions) -> Result<()> {
+        encode_with_options(self, w, options)
+    }
+
     /// Creates a sourcemap index from a reader over a JSON byte slice in UTF-8
     /// format.  Optionally a "garbage header" as defined by the
     /// sourcemap draft specification is supported.  In case a regular
@@ -1102,6 +2105,7 @@ impl SourceMapIndex {
             x_facebook_offsets: None,
             x_metro_module_paths: None,
             debug_id: None,
+            extensions: Extensions::default(),
         }
     }
 
@@ -1124,6 +2128,7 @@ impl SourceMapIndex {
             x_facebook_offsets,
             x_metro_module_paths,
             debug_id: None,
+            extensions: Extensions::default(),
         }
     }
 
@@ -1142,6 +2147,23 @@ impl SourceMapIndex {
         self
     }
 
+    /// Adds the given extension fields to the sourcemap index.
+    pub(crate) fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Returns the extension fields of the sourcemap index.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns the extension fields of the sourcemap index for
+    /// modification.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     /// Returns the embedded filename in case there is one.
     pub fn get_file(&self) -> Option<&str> {
         self.file.as_ref().map(|x| &x[..])
@@ -1200,18 +2222,100 @@ impl SourceMapIndex {
     /// If a sourcemap is encountered that is not embedded but just
     /// externally referenced it is silently skipped.
     pub fn lookup_token(&self, line: u32, col: u32) -> Option<Token<'_>> {
-        let (_section_idx, section) =
-            greatest_lower_bound(&self.sections, &(line, col), SourceMapSection::get_offset)?;
-        let map = section.get_sourcemap()?;
+        self.lookup_token_with_options(line, col, &LookupOptions::default())
+    }
+
+    /// Looks up the closest token to a given line and column with the given
+    /// options.
+    ///
+    /// With [`Bias::LeastUpperBound`] the token may come from one of the
+    /// following sections.  See [`SourceMap::lookup_token_with_options`] for
+    /// details.
+    pub fn lookup_token_with_options(
+        &self,
+        line: u32,
+        col: u32,
+        options: &LookupOptions,
+    ) -> Option<Token<'_>> {
+        let lower_bound =
+            greatest_lower_bound(&self.sections, &(line, col), SourceMapSection::get_offset);
+        if let Some((_, section)) = lower_bound {
+            if let Some(map) = section.get_sourcemap() {
+                let (off_line, off_col) = section.get_offset();
+                let token = map.lookup_token_with_options(
+                    line - off_line,
+                    if line == off_line { col - off_col } else { col },
+                    options,
+                );
+                if token.is_some() {
+                    return token;
+                }
+            }
+        }
+        if options.bias == Bias::GreatestLowerBound {
+            return None;
+        }
+
+        // the closest token after the position is the first token of the
+        // next section that has one
+        let next_idx = lower_bound.map_or(0, |(idx, _)| idx + 1);
+        let first = LookupOptions {
+            bias: Bias::LeastUpperBound,
+            ..Default::default()
+        };
+        let (section, token) = self.sections[next_idx..].iter().find_map(|section| {
+            let token = section
+                .get_sourcemap()?
+                .lookup_token_with_options(0, 0, &first)?;
+            Some((section, token))
+        })?;
         let (off_line, off_col) = section.get_offset();
-        map.lookup_token(
+        let token_pos = match token.get_dst() {
+            (0, token_col) => (off_line, off_col + token_col),
+            (token_line, token_col) => (off_line + token_line, token_col),
+        };
+        Some(token).filter(|_| options.accepts((line, col), token_pos, false))
+    }
+
+    /// Looks up the original frames for a given line and column.
+    ///
+    /// This requires that the referenced sourcemaps are actually loaded.
+    /// See [`SourceMap::lookup_frames`] for details.
+    pub fn lookup_frames(&self, line: u32, col: u32) -> Vec<OriginalFrame<'_>> {
+        let (_section_idx, section) = match greatest_lower_bound(
+            &self.sections,
+            &(line, col),
+            SourceMapSection::get_offset,
+        ) {
+            Some(rv) => rv,
+            None => return vec![],
+        };
+        let map = match section.get_sourcemap() {
+            Some(map) => map,
+            None => return vec![],
+        };
+        let (off_line, off_col) = section.get_offset();
+        map.lookup_frames(
             line - off_line,
             if line == off_line { col - off_col } else { col },
         )
     }
 
+    /// Checks the index and all embedded sourcemaps for problems that the
+    /// decoder accepts.
+    ///
+    /// In addition to the checks of [`SourceMap::validate`] this reports
+    /// sections that overlap with the following section.  The positions of
+    /// diagnostics of embedded sourcemaps are relative to the index.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate_index(self)
+    }
+
     /// Flattens an indexed sourcemap into a regular one.  This requires
-    /// that all referenced sourcemaps are attached.
+    /// that all referenced sourcemaps are attached, see
+    /// [`resolve_sections`](Self::resolve_sections) and
+    /// [`flatten_with`](Self::flatten_with) for index maps with `url`
+    /// sections.
     pub fn flatten(&self) -> Result<SourceMap> {
         let mut builder = SourceMapBuilder::new(self.get_file());
 
@@ -1247,6 +2351,19 @@ impl SourceMapIndex {
                 }
             }
 
+            for (original_id, scope) in map.original_scopes.iter().enumerate() {
+                if let (Some(scope), Some(&src_id)) = (scope, src_id_map.get(original_id)) {
+                    builder.set_original_scope(src_id, Some(scope.clone()));
+                }
+            }
+
+            for range in map.generated_ranges() {
+                let mut range = range.clone();
+                range.offset(off_line, off_col);
+                range.remap_sources(&|src_id| src_id_map.get(src_id as usize).copied());
+                builder.add_generated_range(range);
+            }
+
             let mut name_id_map = Vec::<u32>::with_capacity(map.names().count());
 
             for (original_id, name) in map.names().enumerate() {
@@ -1293,7 +2410,65 @@ impl SourceMapIndex {
             }
         }
 
-        Ok(builder.into_sourcemap())
+        let mut sm = builder.into_sourcemap();
+        sm.extensions = self.extensions.clone();
+        Ok(sm)
+    }
+
+    /// Resolves sections that only reference their sourcemap by URL.
+    ///
+    /// The callback is invoked with the URL of every section that has no
+    /// embedded sourcemap and the returned map is attached to the section.
+    /// Sections for which the callback returns `None` stay unresolved.  The
+    /// first error returned by the callback is passed on.
+    ///
+    /// ```rust
+    /// use sourcemap::{DecodedMap, SourceMap, SourceMapIndex};
+    /// let input: &[_] = br#"{
+    ///     "version": 3,
+    ///     "sections": [{"offset": {"line": 0, "column": 0}, "url": "a.js.map"}]
+    /// }"#;
+    /// let mut smi = SourceMapIndex::from_slice(input).unwrap();
+    /// smi.resolve_sections(|url| {
+    ///     assert_eq!(url, "a.js.map");
+    ///     let sm = SourceMap::from_slice(br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA"}"#)?;
+    ///     Ok(Some(DecodedMap::Regular(sm)))
+    /// })
+    /// .unwrap();
+    /// assert_eq!(smi.flatten().unwrap().get_source(0), Some("a.js"));
+    /// ```
+    pub fn resolve_sections<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<Option<DecodedMap>>,
+    {
+        for section in &mut self.sections {
+            if section.map.is_some() {
+                continue;
+            }
+            if let Some(url) = section.url.as_deref() {
+                section.set_sourcemap(f(url)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Flattens an indexed sourcemap into a regular one, loading sections
+    /// that are only referenced by URL through the given resolver.
+    ///
+    /// Section URLs are passed to the resolver unchanged.  Unlike
+    /// [`flatten`](Self::flatten) this fails with `CannotFlatten` only if a
+    /// section cannot be found by the resolver either.
+    pub fn flatten_with<R: Resolver + ?Sized>(&self, resolver: &mut R) -> Result<SourceMap> {
+        if self.sections.iter().all(|section| section.map.is_some()) {
+            return self.flatten();
+        }
+        let mut smi = self.clone();
+        smi.resolve_sections(|url| {
+            Ok(resolver
+                .load_sourcemap(url)?
+                .map(|sm| Arc::try_unwrap(sm).unwrap_or_else(|sm| (*sm).clone())))
+        })?;
+        smi.flatten()
     }
 
     /// Flattens an indexed sourcemap into a regular one and automatically
@@ -1548,8 +2723,12 @@ mod tests {
             sources: vec![],
             sources_prefixed: None,
             sources_content: vec![],
+            original_scopes: vec![],
+            generated_ranges: vec![],
             ignore_list: BTreeSet::new(),
             debug_id: None,
+            extensions: Default::default(),
+            reverse_index: Default::default(),
         });
 
         assert!(decoded_map.debug_id().is_none());
@@ -1566,8 +2745,12 @@ mod tests {
                 sources: vec![],
                 sources_prefixed: None,
                 sources_content: vec![],
+                original_scopes: vec![],
+                generated_ranges: vec![],
                 ignore_list: BTreeSet::new(),
                 debug_id: Some(DEBUG_ID.parse().expect("valid debug id")),
+                extensions: Default::default(),
+                reverse_index: Default::default(),
             })
         );
 
@@ -1587,6 +2770,7 @@ mod tests {
             x_facebook_offsets: None,
             x_metro_module_paths: None,
             debug_id: None,
+            extensions: Default::default(),
         });
 
         assert!(decoded_map.debug_id().is_none());
@@ -1601,6 +2785,7 @@ mod tests {
                 x_facebook_offsets: None,
                 x_metro_module_paths: None,
                 debug_id: Some(DEBUG_ID.parse().expect("valid debug id")),
+                extensions: Default::default(),
             })
         );
 
@@ -124,7 +124,7 @@ pub fn greatest_lower_bound<'a, T, K: Ord, F: Fn(&'a T) -> K>(
         Err(index) => {
             // If there is no match, then we know for certain that the index is where we should
             // insert a new token, and that the token directly before is the greatest lower bound.
-            return slice.get(index.checked_sub(1)?).map(|res| (index, res));
+            return slice.get(index.checked_sub(1)?).map(|res| (index - 1, res));
         }
     };
 
@@ -207,28 +207,34 @@ mod tests {
         let cmp = |&(i, _id)| i;
 
         let haystack = vec![(1, 1)];
-        assert_eq!(greatest_lower_bound(&haystack, &1, cmp).unwrap().1, &(1, 1));
-        assert_eq!(greatest_lower_bound(&haystack, &2, cmp).unwrap().1, &(1, 1));
+        assert_eq!(greatest_lower_bound(&haystack, &1, cmp), Some((0, &(1, 1))));
+        assert_eq!(greatest_lower_bound(&haystack, &2, cmp), Some((0, &(1, 1))));
         assert_eq!(greatest_lower_bound(&haystack, &0, cmp), None);
 
         let haystack = vec![(1, 1), (1, 2)];
-        assert_eq!(greatest_lower_bound(&haystack, &1, cmp).unwrap().1, &(1, 1));
-        assert_eq!(greatest_lower_bound(&haystack, &2, cmp).unwrap().1, &(1, 2));
+        assert_eq!(greatest_lower_bound(&haystack, &1, cmp), Some((0, &(1, 1))));
+        assert_eq!(greatest_lower_bound(&haystack, &2, cmp), Some((1, &(1, 2))));
         assert_eq!(greatest_lower_bound(&haystack, &0, cmp), None);
 
         let haystack = vec![(1, 1), (1, 2), (1, 3)];
-        assert_eq!(greatest_lower_bound(&haystack, &1, cmp).unwrap().1, &(1, 1));
-        assert_eq!(greatest_lower_bound(&haystack, &2, cmp).unwrap().1, &(1, 3));
+        assert_eq!(greatest_lower_bound(&haystack, &1, cmp), Some((0, &(1, 1))));
+        assert_eq!(greatest_lower_bound(&haystack, &2, cmp), Some((2, &(1, 3))));
         assert_eq!(greatest_lower_bound(&haystack, &0, cmp), None);
 
         let haystack = vec![(1, 1), (1, 2), (1, 3), (1, 4)];
-        assert_eq!(greatest_lower_bound(&haystack, &1, cmp).unwrap().1, &(1, 1));
-        assert_eq!(greatest_lower_bound(&haystack, &2, cmp).unwrap().1, &(1, 4));
+        assert_eq!(greatest_lower_bound(&haystack, &1, cmp), Some((0, &(1, 1))));
+        assert_eq!(greatest_lower_bound(&haystack, &2, cmp), Some((3, &(1, 4))));
         assert_eq!(greatest_lower_bound(&haystack, &0, cmp), None);
 
         let haystack = vec![(1, 1), (1, 2), (1, 3), (1, 4), (1, 5)];
-        assert_eq!(greatest_lower_bound(&haystack, &1, cmp).unwrap().1, &(1, 1));
-        assert_eq!(greatest_lower_bound(&haystack, &2, cmp).unwrap().1, &(1, 5));
+        assert_eq!(greatest_lower_bound(&haystack, &1, cmp), Some((0, &(1, 1))));
+        assert_eq!(greatest_lower_bound(&haystack, &2, cmp), Some((4, &(1, 5))));
         assert_eq!(greatest_lower_bound(&haystack, &0, cmp), None);
+
+        let haystack = vec![(1, 1), (3, 2), (3, 3), (5, 4)];
+        assert_eq!(greatest_lower_bound(&haystack, &2, cmp), Some((0, &(1, 1))));
+        assert_eq!(greatest_lower_bound(&haystack, &3, cmp), Some((1, &(3, 2))));
+        assert_eq!(greatest_lower_bound(&haystack, &4, cmp), Some((2, &(3, 3))));
+        assert_eq!(greatest_lower_bound(&haystack, &6, cmp), Some((3, &(5, 4))));
     }
 }
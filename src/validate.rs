@@ -0,0 +1,322 @@
+use std::fmt;
+
+use crate::decoder::MappingsIter;
+use crate::jsontypes::RawSourceMap;
+use crate::types::{DecodedMap, SourceMap, SourceMapIndex};
+
+/// The kind of problem found while validating a sourcemap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A token points to a line past the end of its source.
+    SourceLineOutOfBounds {
+        /// the source of the token
+        src_id: u32,
+        /// the 0-indexed line of the token
+        src_line: u32,
+        /// the number of lines of the source
+        line_count: u32,
+    },
+    /// A token points to a column past the end of its source line.
+    SourceColumnOutOfBounds {
+        /// the source of the token
+        src_id: u32,
+        /// the 0-indexed line of the token
+        src_line: u32,
+        /// the 0-indexed column of the token
+        src_col: u32,
+        /// the length of the line in UTF-16 code units
+        line_length: u32,
+    },
+    /// The name of a token does not match the source at its position.
+    NameMismatch {
+        /// the name of the token
+        name: String,
+        /// the source text at the position of the token
+        found: String,
+    },
+    /// A mapping is not sorted by column within its line.
+    UnsortedMapping,
+    /// More than one mapping exists for the same generated position.
+    DuplicateMapping,
+    /// An `ignoreList` entry references a non existing source.
+    IgnoreListOutOfBounds(u32),
+    /// `sourcesContent` has a different number of entries than `sources`.
+    SourcesContentLengthMismatch {
+        /// the number of sources
+        sources: u32,
+        /// the number of source contents
+        sources_content: u32,
+    },
+    /// A section of an index map overlaps with the following section.
+    OverlappingSections {
+        /// the index of the overlapping section
+        section: u32,
+    },
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DiagnosticKind::SourceLineOutOfBounds {
+                src_id,
+                src_line,
+                line_count,
+            } => write!(
+                f,
+                "line {src_line} is out of bounds for source {src_id} with {line_count} lines"
+            ),
+            DiagnosticKind::SourceColumnOutOfBounds {
+                src_id,
+                src_line,
+                src_col,
+                line_length,
+            } => write!(
+                f,
+                "column {src_col} is out of bounds for line {src_line} of source {src_id} \
+                 with length {line_length}"
+            ),
+            DiagnosticKind::NameMismatch {
+                ref name,
+                ref found,
+            } => write!(f, "name {name:?} does not match source text {found:?}"),
+            DiagnosticKind::UnsortedMapping => write!(f, "mapping is not sorted by column"),
+            DiagnosticKind::DuplicateMapping => write!(f, "duplicate mapping"),
+            DiagnosticKind::IgnoreListOutOfBounds(src_id) => {
+                write!(f, "ignore list references non existing source {src_id}")
+            }
+            DiagnosticKind::SourcesContentLengthMismatch {
+                sources,
+                sources_content,
+            } => write!(
+                f,
+                "{sources_content} sources contents do not match {sources} sources"
+            ),
+            DiagnosticKind::OverlappingSections { section } => {
+                write!(f, "section {section} overlaps with the following section")
+            }
+        }
+    }
+}
+
+/// A problem found while validating a sourcemap.
+///
+/// See [`SourceMap::validate`] and [`decode_strict`](crate::decode_strict).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// the 0-indexed generated line and column the problem was found at
+    pub position: Option<(u32, u32)>,
+    /// what is wrong
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    fn new(position: Option<(u32, u32)>, kind: DiagnosticKind) -> Diagnostic {
+        Diagnostic { position, kind }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((line, col)) = self.position {
+            write!(f, "{line}:{col}: ")?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+/// Moves the positions of diagnostics of a section into the index map.
+fn offset_diagnostics(diagnostics: &mut [Diagnostic], (off_line, off_col): (u32, u32)) {
+    for diagnostic in diagnostics {
+        if let Some((line, col)) = diagnostic.position.as_mut() {
+            if *line == 0 {
+                *col += off_col;
+            }
+            *line += off_line;
+        }
+    }
+}
+
+pub fn validate_sourcemap(sm: &SourceMap) -> Vec<Diagnostic> {
+    let mut rv = vec![];
+
+    let sources_content = sm.sources_content.len() as u32;
+    if sources_content != 0 && sources_content != sm.get_source_count() {
+        rv.push(Diagnostic::new(
+            None,
+            DiagnosticKind::SourcesContentLengthMismatch {
+                sources: sm.get_source_count(),
+                sources_content,
+            },
+        ));
+    }
+
+    for &src_id in sm.ignore_list() {
+        if src_id >= sm.get_source_count() {
+            rv.push(Diagnostic::new(
+                None,
+                DiagnosticKind::IgnoreListOutOfBounds(src_id),
+            ));
+        }
+    }
+
+    let mut prev = None;
+    for token in sm.tokens() {
+        let position = Some(token.get_dst());
+        if prev == position {
+            rv.push(Diagnostic::new(position, DiagnosticKind::DuplicateMapping));
+        }
+        prev = position;
+
+        let view = match token.get_source_view() {
+            Some(view) => view,
+            None => continue,
+        };
+        let (src_id, src_line, src_col) = (
+            token.get_src_id(),
+            token.get_src_line(),
+            token.get_src_col(),
+        );
+        let line = match view.get_line(src_line) {
+            Some(line) => line,
+            None => {
+                rv.push(Diagnostic::new(
+                    position,
+                    DiagnosticKind::SourceLineOutOfBounds {
+                        src_id,
+                        src_line,
+                        line_count: view.line_count() as u32,
+                    },
+                ));
+                continue;
+            }
+        };
+        let line_length = line.encode_utf16().count() as u32;
+        if src_col > line_length {
+            rv.push(Diagnostic::new(
+                position,
+                DiagnosticKind::SourceColumnOutOfBounds {
+                    src_id,
+                    src_line,
+                    src_col,
+                    line_length,
+                },
+            ));
+            continue;
+        }
+
+        if let Some(name) = token.get_name() {
+            let span = name.encode_utf16().count() as u32;
+            let found = view
+                .get_line_slice(src_line, src_col, span)
+                .unwrap_or_default();
+            if found != name {
+                rv.push(Diagnostic::new(
+                    position,
+                    DiagnosticKind::NameMismatch {
+                        name: name.to_string(),
+                        found: found.to_string(),
+                    },
+                ));
+            }
+        }
+    }
+
+    rv
+}
+
+/// Returns the generated position of the last token of a map.
+fn last_position(map: &DecodedMap) -> Option<(u32, u32)> {
+    match map {
+        DecodedMap::Regular(sm) => sm.tokens.last().map(|t| (t.dst_line, t.dst_col)),
+        DecodedMap::Hermes(smh) => smh.tokens.last().map(|t| (t.dst_line, t.dst_col)),
+        DecodedMap::Index(smi) => smi
+            .sections()
+            .filter_map(|section| {
+                let (off_line, off_col) = section.get_offset();
+                let (line, col) = last_position(section.get_sourcemap()?)?;
+                Some((line + off_line, if line == 0 { col + off_col } else { col }))
+            })
+            .last(),
+    }
+}
+
+pub fn validate_index(smi: &SourceMapIndex) -> Vec<Diagnostic> {
+    let mut rv = vec![];
+    let sections: Vec<_> = smi.sections().collect();
+
+    for (idx, section) in sections.iter().enumerate() {
+        let map = match section.get_sourcemap() {
+            Some(map) => map,
+            None => continue,
+        };
+
+        if let Some(next) = sections.get(idx + 1) {
+            let (off_line, off_col) = section.get_offset();
+            let end = match last_position(map) {
+                Some((0, col)) => Some((off_line, col + off_col)),
+                Some((line, col)) => Some((line + off_line, col)),
+                None => None,
+            };
+            if next.get_offset() == section.get_offset()
+                || end.is_some_and(|end| end >= next.get_offset())
+            {
+                rv.push(Diagnostic::new(
+                    Some(next.get_offset()),
+                    DiagnosticKind::OverlappingSections {
+                        section: idx as u32,
+                    },
+                ));
+            }
+        }
+
+        let mut diagnostics = validate_decoded(map);
+        offset_diagnostics(&mut diagnostics, section.get_offset());
+        rv.extend(diagnostics);
+    }
+
+    rv
+}
+
+pub fn validate_decoded(map: &DecodedMap) -> Vec<Diagnostic> {
+    match map {
+        DecodedMap::Regular(sm) => validate_sourcemap(sm),
+        DecodedMap::Hermes(smh) => validate_sourcemap(smh),
+        DecodedMap::Index(smi) => validate_index(smi),
+    }
+}
+
+/// Checks the order of the raw mappings before they are sorted by the
+/// decoder.
+///
+/// Decoding errors are ignored here as they are reported by the decoder.
+pub fn validate_raw_order(rsm: &RawSourceMap, offset: (u32, u32), rv: &mut Vec<Diagnostic>) {
+    for section in rsm.sections.iter().flatten() {
+        if let Some(ref map) = section.map {
+            let (off_line, off_col) = (section.offset.line, section.offset.column);
+            let mut diagnostics = vec![];
+            validate_raw_order(map, (off_line, off_col), &mut diagnostics);
+            offset_diagnostics(&mut diagnostics, offset);
+            rv.extend(diagnostics);
+        }
+    }
+
+    let mut diagnostics = vec![];
+    let mut prev = None;
+    let iter = MappingsIter::new(rsm.mappings.as_deref().unwrap_or_default(), None, !0, !0);
+    for token in iter {
+        let token = match token {
+            Ok(token) => token,
+            Err(_) => break,
+        };
+        let position = (token.dst_line, token.dst_col);
+        if prev.is_some_and(|prev| position < prev) {
+            diagnostics.push(Diagnostic::new(
+                Some(position),
+                DiagnosticKind::UnsortedMapping,
+            ));
+        }
+        prev = Some(position);
+    }
+    offset_diagnostics(&mut diagnostics, offset);
+    rv.extend(diagnostics);
+}
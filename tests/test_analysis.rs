@@ -0,0 +1,72 @@
+use serde_json::Value;
+use sourcemap::analysis::analyze_bundle;
+use sourcemap::{SourceMap, SourceView};
+
+#[test]
+fn test_analyze_bundle() {
+    let generated = SourceView::new(
+        "  x(\"ä\");y()\r\n/* banner */ z();\n\nq()\n//# sourceMappingURL=bundle.js.map\n".into(),
+    );
+    let sm = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sourceRoot": "webpack:///",
+            "sources": ["./src/x.js", "./node_modules/y/index.js", "./src/z.js"],
+            "names": [],
+            "mappings": "EAAA,OCAA;aCAA;;A"
+        }"#,
+    )
+    .unwrap();
+
+    let report = analyze_bundle(&generated, &sm);
+    assert_eq!(report.total_bytes, generated.source().len() as u64);
+    assert_eq!(
+        report.mapped_bytes
+            + report.unmapped_bytes
+            + report.whitespace_bytes
+            + report.source_mapping_url_bytes,
+        report.total_bytes
+    );
+
+    let files: Vec<_> = report
+        .files
+        .iter()
+        .map(|file| (file.path.as_str(), file.bytes))
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            // `x("ä");` is 7 UTF-16 code units but 8 bytes
+            ("webpack:///./src/x.js", 8),
+            ("webpack:///./src/z.js", 4),
+            ("webpack:///./node_modules/y/index.js", 3),
+        ]
+    );
+    // the banner and the line mapped without a source
+    assert_eq!(report.unmapped_bytes, 10 + 3);
+    // leading spaces, the spaces in and after the banner and line terminators
+    assert_eq!(report.whitespace_bytes, 2 + 3 + 2 + 1 + 1 + 1 + 1);
+    assert_eq!(report.source_mapping_url_bytes, 34);
+
+    let tree = &report.tree;
+    assert_eq!(tree.bytes, 15);
+    let names: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["webpack:"]);
+    assert_eq!(tree.get(&["webpack:", "src"]).unwrap().bytes, 12);
+    assert_eq!(
+        tree.get(&["webpack:", "node_modules", "y", "index.js"])
+            .unwrap()
+            .bytes,
+        3
+    );
+
+    let mut json = vec![];
+    report.to_writer(&mut json).unwrap();
+    let json: Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["sourceMappingUrlBytes"], 34);
+    assert_eq!(json["files"][0]["path"], "webpack:///./src/x.js");
+    assert_eq!(
+        json["tree"]["children"][0]["children"][0]["name"],
+        "node_modules"
+    );
+}
@@ -0,0 +1,107 @@
+use sourcemap::{BinarySourceMap, Error, SourceMap, SourceMapBuilder};
+
+fn sample_sourcemap() -> SourceMap {
+    let input: &[_] = br#"{
+        "version": 3,
+        "file": "bundle.js",
+        "sourceRoot": "webpack:///",
+        "sources": ["a.js", "b.js"],
+        "sourcesContent": ["var a = 1;", null],
+        "names": ["a", "b"],
+        "rangeMappings": "B",
+        "mappings": "AAAA,IAAIA,C;ACAA,IACIC",
+        "ignoreList": [1],
+        "debugId": "00000000-0000-0000-0000-000000000001"
+    }"#;
+    SourceMap::from_slice(input).unwrap()
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    let sm = sample_sourcemap();
+    let mut out = vec![];
+    sm.to_binary_writer(&mut out).unwrap();
+
+    let cached = SourceMap::from_binary_slice(&out).unwrap();
+    assert_eq!(cached, sm);
+    assert_eq!(cached.get_source(1), Some("webpack:///b.js"));
+    assert_eq!(cached.get_source_contents(0), Some("var a = 1;"));
+    assert_eq!(cached.get_debug_id(), sm.get_debug_id());
+    assert!(cached.get_token(0).unwrap().is_range());
+    assert_eq!(
+        cached.lookup_token(1, 5).unwrap().to_tuple(),
+        ("webpack:///b.js", 1, 8, Some("b"))
+    );
+}
+
+#[test]
+fn test_binary_view() {
+    let sm = sample_sourcemap();
+    let mut out = vec![];
+    sm.to_binary_writer(&mut out).unwrap();
+
+    let view = BinarySourceMap::from_slice(&out).unwrap();
+    assert_eq!(view.get_file(), Some("bundle.js"));
+    assert_eq!(view.get_source_root(), Some("webpack:///"));
+    assert_eq!(view.get_debug_id(), sm.get_debug_id());
+    assert_eq!(view.get_token_count(), sm.get_token_count());
+    assert_eq!(view.get_source(1).as_deref(), Some("webpack:///b.js"));
+    assert_eq!(view.get_source(2), None);
+    assert_eq!(view.get_source_contents(0), Some("var a = 1;"));
+    assert_eq!(view.get_source_contents(1), None);
+    assert_eq!(view.get_name(1), Some("b"));
+    assert_eq!(view.ignore_list().collect::<Vec<_>>(), vec![1]);
+
+    for line in 0..3 {
+        for col in 0..12 {
+            let expected = sm.lookup_token(line, col).map(|token| {
+                let mut raw = token.get_raw_token();
+                raw.src_col = token.get_src_col();
+                raw
+            });
+            assert_eq!(view.lookup_token(line, col), expected);
+        }
+    }
+
+    assert_eq!(view.to_sourcemap().unwrap(), sm);
+}
+
+#[test]
+fn test_binary_without_contents() {
+    let sm = SourceMapBuilder::new(None).into_sourcemap();
+    let mut out = vec![];
+    sm.to_binary_writer(&mut out).unwrap();
+    assert_eq!(SourceMap::from_binary_slice(&out).unwrap(), sm);
+
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 1, 1, Some("a.js"), None, false);
+    let sm = builder.into_sourcemap();
+    let mut out = vec![];
+    sm.to_binary_writer(&mut out).unwrap();
+    assert_eq!(SourceMap::from_binary_slice(&out).unwrap(), sm);
+}
+
+#[test]
+fn test_binary_invalid() {
+    let mut out = vec![];
+    sample_sourcemap().to_binary_writer(&mut out).unwrap();
+
+    assert!(matches!(
+        SourceMap::from_binary_slice(b"{\"version\":3}"),
+        Err(Error::InvalidBinaryCacheMagic)
+    ));
+
+    let mut bad_version = out.clone();
+    bad_version[4] = 42;
+    assert!(matches!(
+        SourceMap::from_binary_slice(&bad_version),
+        Err(Error::UnsupportedBinaryCacheVersion(42))
+    ));
+
+    for len in [10, 60, out.len() - 1] {
+        assert!(matches!(
+            SourceMap::from_binary_slice(&out[..len]),
+            Err(Error::InvalidBinaryCache)
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use sourcemap::{BorrowedSourceMap, SourceMap, Token, TokenStore};
+
+#[test]
+fn test_borrowed_matches_regular() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "file": "bundle.js",
+        "sourceRoot": "/src",
+        "sources": ["a.js", "b\/c.js"],
+        "sourcesContent": ["var a;\nvar b;", null],
+        "names": ["a", "b"],
+        "mappings": "AAAA,IAAIA;ACAA,IACIC",
+        "ignoreList": [1]
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    let borrowed = BorrowedSourceMap::from_slice(input).unwrap();
+
+    assert_eq!(borrowed.get_file(), Some("bundle.js"));
+    assert_eq!(borrowed.get_source(0), Some("/src/a.js"));
+    assert_eq!(borrowed.get_source(1), Some("/src/b/c.js"));
+    assert_eq!(borrowed.get_token_count(), sm.get_token_count());
+    for (a, b) in borrowed.tokens().zip(sm.tokens()) {
+        assert_eq!(a.get_raw_token(), b.get_raw_token());
+        assert_eq!(a.to_tuple(), b.to_tuple());
+    }
+
+    let token = borrowed.lookup_token(1, 6).unwrap();
+    assert_eq!(token.to_tuple(), ("/src/b/c.js", 1, 8, Some("b")));
+    assert_eq!(token.to_string(), "/src/b/c.js:1:8 name=b");
+
+    let view = borrowed
+        .lookup_token(0, 0)
+        .unwrap()
+        .get_source_view()
+        .unwrap();
+    assert_eq!(view.get_line(1), Some("var b;"));
+    assert_eq!(borrowed.get_source_contents(1), None);
+
+    assert_eq!(borrowed.to_sourcemap(), sm);
+}
+
+#[test]
+fn test_borrowed_range_mappings() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "names": [],
+        "rangeMappings": "B",
+        "mappings": "AAAA"
+    }"#;
+    let sm = BorrowedSourceMap::from_slice(input).unwrap();
+    let token = sm.lookup_token(0, 7).unwrap();
+    assert!(token.is_range());
+    assert_eq!(token.get_src(), (0, 7));
+
+    // a range token from a previous line is not offset
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "names": [],
+        "rangeMappings": "B",
+        "mappings": "UAAA"
+    }"#;
+    let sm = BorrowedSourceMap::from_slice(input).unwrap();
+    let token = sm.lookup_token(1, 2).unwrap();
+    assert!(token.is_range());
+    assert_eq!(token.get_src(), (0, 0));
+}
+
+#[test]
+fn test_borrowed_tokens_are_tokens() {
+    fn describe<M: TokenStore>(token: Token<'_, M>) -> String {
+        format!("{:#}", token)
+    }
+
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "sourcesContent": ["foo();"],
+        "names": ["foo"],
+        "mappings": "AAAAA,IAAI"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    let borrowed = BorrowedSourceMap::from_slice(input).unwrap();
+    for (a, b) in borrowed.tokens().zip(sm.tokens()) {
+        assert_eq!(describe(a), describe(b));
+        assert_eq!(a.get_dst_end(None), b.get_dst_end(None));
+    }
+
+    let token = borrowed.lookup_token(0, 0).unwrap();
+    let view = token.get_source_view().unwrap();
+    assert_eq!(view.get_line(0), Some("foo();"));
+    assert_eq!(token.sourcemap().get_name_count(), 1);
+}
+
+#[test]
+fn test_borrowed_rejects_index() {
+    let input: &[_] = br#"{"version": 3, "sections": []}"#;
+    assert!(BorrowedSourceMap::from_slice(input).is_err());
+}
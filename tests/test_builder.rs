@@ -1,4 +1,4 @@
-use sourcemap::SourceMapBuilder;
+use sourcemap::{DecodedMap, SourceMapBuilder, SourceMapIndexBuilder};
 
 #[test]
 fn test_builder_into_sourcemap() {
@@ -18,3 +18,60 @@ fn test_builder_into_sourcemap() {
     sm.to_writer(&mut output).unwrap();
     assert_eq!(output, expected);
 }
+
+#[test]
+fn test_index_builder_offsets() {
+    let chunk = |source: &str| {
+        let mut builder = SourceMapBuilder::new(None);
+        builder.add(0, 0, 0, 0, Some(source), None, false);
+        builder.add(1, 2, 1, 0, Some(source), None, false);
+        DecodedMap::Regular(builder.into_sourcemap())
+    };
+
+    let mut builder = SourceMapIndexBuilder::new(Some("bundle.js"));
+    assert_eq!(builder.add_chunk("/* banner */", None), (0, 0));
+    assert_eq!(
+        builder.add_chunk("a();\r\n  b();", Some(chunk("a.js"))),
+        (0, 12)
+    );
+    assert_eq!(
+        builder.add_chunk("c👌();\n  d();\n", Some(chunk("c.js"))),
+        (1, 6)
+    );
+    assert_eq!(builder.add_chunk_with_url("e();", "e.js.map"), (3, 0));
+    assert_eq!(builder.get_offset(), (3, 4));
+
+    let index = builder.into_index();
+    assert_eq!(index.get_file(), Some("bundle.js"));
+    let offsets: Vec<_> = index.sections().map(|s| s.get_offset()).collect();
+    assert_eq!(offsets, vec![(0, 12), (1, 6), (3, 0)]);
+    assert_eq!(index.get_section(2).unwrap().get_url(), Some("e.js.map"));
+
+    assert_eq!(
+        index.lookup_token(1, 7).unwrap().to_tuple(),
+        ("c.js", 0, 0, None)
+    );
+    assert_eq!(
+        index.lookup_token(2, 2).unwrap().to_tuple(),
+        ("c.js", 1, 0, None)
+    );
+}
+
+#[test]
+fn test_index_builder_into_sourcemap() {
+    let mut builder = SourceMapIndexBuilder::new(None);
+    builder.add_chunk("x", None);
+
+    let mut chunk = SourceMapBuilder::new(None);
+    chunk.add(0, 0, 3, 4, Some("a.js"), Some("foo"), false);
+    builder.add_chunk("foo()", Some(DecodedMap::Regular(chunk.into_sourcemap())));
+
+    let sm = builder.into_sourcemap().unwrap();
+    let token = sm.get_token(0).unwrap();
+    assert_eq!(token.get_dst(), (0, 1));
+    assert_eq!(token.to_tuple(), ("a.js", 3, 4, Some("foo")));
+
+    let mut builder = SourceMapIndexBuilder::new(None);
+    builder.add_chunk_with_url("foo()", "a.js.map");
+    assert!(builder.into_sourcemap().is_err());
+}
@@ -0,0 +1,129 @@
+use sourcemap::{SourceMap, SourceMapBuilder};
+
+fn compiled_sourcemap() -> SourceMap {
+    let mut builder = SourceMapBuilder::new(Some("a.js"));
+    let src_id = builder.add_source("a.ts");
+    builder.set_source_contents(src_id, Some("const answer: number = 42;"));
+    builder.add_to_ignore_list(src_id);
+    builder.add(0, 0, 0, 0, Some("a.ts"), None, false);
+    builder.add(0, 6, 0, 6, Some("a.ts"), Some("answer"), false);
+    builder.add(2, 0, 1, 0, Some("a.ts"), None, false);
+    builder.into_sourcemap()
+}
+
+#[test]
+fn test_compose_basic() {
+    let mut builder = SourceMapBuilder::new(Some("a.min.js"));
+    builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+    builder.add(0, 4, 0, 6, Some("a.js"), Some("a"), false);
+    builder.add(0, 10, 1, 3, Some("a.js"), None, false);
+    builder.add_raw(0, 20, 0, 0, None, None, false);
+    let minified = builder.into_sourcemap();
+
+    let sm = minified.compose(&[&compiled_sourcemap()]);
+    assert_eq!(sm.get_file(), Some("a.min.js"));
+    assert_eq!(sm.get_token_count(), 4);
+
+    let token = sm.lookup_token(0, 0).unwrap();
+    assert_eq!(token.to_tuple(), ("a.ts", 0, 0, None));
+    let token = sm.lookup_token(0, 4).unwrap();
+    assert_eq!(token.to_tuple(), ("a.ts", 0, 6, Some("answer")));
+    // line 1 of a.js has no mappings
+    assert!(!sm.lookup_token(0, 10).unwrap().has_source());
+    assert!(!sm.lookup_token(0, 20).unwrap().has_source());
+
+    assert_eq!(
+        sm.get_source_contents(0),
+        Some("const answer: number = 42;")
+    );
+    assert_eq!(sm.ignore_list().collect::<Vec<_>>(), vec![&0]);
+}
+
+#[test]
+fn test_compose_chain() {
+    let mut builder = SourceMapBuilder::new(Some("a.ts"));
+    builder.add(0, 6, 3, 2, Some("a.coffee"), None, false);
+    builder.add(1, 0, 4, 0, Some("a.coffee"), None, false);
+    let original = builder.into_sourcemap();
+
+    let mut builder = SourceMapBuilder::new(Some("a.min.js"));
+    builder.add(0, 0, 0, 6, Some("a.js"), None, false);
+    let minified = builder.into_sourcemap();
+
+    let sm = minified.compose(&[&compiled_sourcemap(), &original]);
+    let token = sm.lookup_token(0, 0).unwrap();
+    assert_eq!(token.to_tuple(), ("a.coffee", 3, 2, Some("answer")));
+    assert!(sm.get_source_contents(0).is_none());
+    assert_eq!(sm.ignore_list().count(), 0);
+
+    let sm = minified.compose(&[]);
+    assert_eq!(
+        sm.lookup_token(0, 0).unwrap().to_tuple(),
+        ("a.js", 0, 6, None)
+    );
+}
+
+#[test]
+fn test_compose_range_mappings() {
+    let mut builder = SourceMapBuilder::new(Some("a.js"));
+    builder.add(0, 0, 3, 0, Some("a.ts"), None, true);
+    builder.add(0, 8, 4, 2, Some("a.ts"), None, false);
+    let compiled = builder.into_sourcemap();
+
+    let mut builder = SourceMapBuilder::new(Some("a.min.js"));
+    builder.add(0, 0, 0, 2, Some("a.js"), None, true);
+    let minified = builder.into_sourcemap();
+
+    let sm = minified.compose(&[&compiled]);
+    assert_eq!(sm.get_token_count(), 2);
+    let token = sm.get_token(0).unwrap();
+    assert_eq!(token.get_dst(), (0, 0));
+    assert_eq!(token.get_src(), (3, 2));
+    assert!(token.is_range());
+    let token = sm.get_token(1).unwrap();
+    assert_eq!(token.get_dst(), (0, 6));
+    assert_eq!(token.get_src(), (4, 2));
+    assert!(!token.is_range());
+}
+
+#[test]
+fn test_compose_multiple_sources() {
+    let mut builder = SourceMapBuilder::new(Some("bundle.js"));
+    let src_id = builder.add_source("./b.js");
+    builder.set_source_contents(src_id, Some("b();"));
+    builder.add(0, 0, 0, 0, Some("webpack:///./a.js"), None, false);
+    builder.add(0, 6, 1, 0, Some("./b.js"), None, false);
+    builder.add(1, 0, 0, 6, Some("webpack:///./a.js"), None, false);
+    let bundle = builder.into_sourcemap();
+
+    let sm = bundle.compose(&[&compiled_sourcemap()]);
+    assert_eq!(sm.get_token_count(), 3);
+    let token = sm.lookup_token(0, 0).unwrap();
+    assert_eq!(token.to_tuple(), ("a.ts", 0, 0, None));
+    // b.js was not produced by the upstream map and passes through
+    let token = sm.lookup_token(0, 6).unwrap();
+    assert_eq!(token.to_tuple(), ("./b.js", 1, 0, None));
+    assert_eq!(sm.get_source_contents(token.get_src_id()), Some("b();"));
+    let token = sm.lookup_token(1, 0).unwrap();
+    assert_eq!(token.to_tuple(), ("a.ts", 0, 6, Some("answer")));
+}
+
+#[test]
+fn test_compose_range_mapping_across_lines() {
+    let mut builder = SourceMapBuilder::new(Some("a.js"));
+    builder.add(0, 10, 3, 0, Some("a.ts"), None, true);
+    builder.add(1, 2, 4, 0, Some("a.ts"), None, false);
+    let compiled = builder.into_sourcemap();
+
+    let mut builder = SourceMapBuilder::new(Some("a.min.js"));
+    builder.add(0, 0, 0, 12, Some("a.js"), None, true);
+    let minified = builder.into_sourcemap();
+
+    // the upstream token on the next line starts at a smaller column and
+    // must not be pulled into the range
+    let sm = minified.compose(&[&compiled]);
+    assert_eq!(sm.get_token_count(), 1);
+    let token = sm.get_token(0).unwrap();
+    assert_eq!(token.get_src(), (3, 2));
+    assert!(token.is_range());
+}
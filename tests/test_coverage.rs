@@ -0,0 +1,66 @@
+use sourcemap::coverage::{remap_coverage, ScriptCoverage};
+use sourcemap::{SourceMapBuilder, SourceView};
+
+#[test]
+fn test_remap_block_coverage() {
+    // function f(a){if(a){x()}else{y()}}
+    // f(1);
+    let generated = SourceView::new("function f(a){if(a){x()}else{y()}}\nf(1);".into());
+    let mut builder = SourceMapBuilder::new(Some("bundle.js"));
+    builder.add(0, 0, 0, 0, Some("f.ts"), None, false);
+    builder.add(0, 14, 1, 2, Some("f.ts"), None, false);
+    builder.add(0, 20, 2, 4, Some("f.ts"), None, false);
+    builder.add(0, 28, 4, 4, Some("f.ts"), None, false);
+    builder.add(0, 33, 6, 0, Some("f.ts"), None, false);
+    builder.add(1, 0, 0, 0, Some("main.ts"), None, false);
+    let sm = builder.into_sourcemap();
+
+    let coverage = ScriptCoverage::from_slice(
+        br#"{
+        "scriptId": "42",
+        "url": "file:///bundle.js",
+        "functions": [
+            {"functionName": "", "isBlockCoverage": true,
+             "ranges": [{"startOffset": 0, "endOffset": 40, "count": 1}]},
+            {"functionName": "f", "isBlockCoverage": true,
+             "ranges": [
+                {"startOffset": 0, "endOffset": 34, "count": 1},
+                {"startOffset": 24, "endOffset": 33, "count": 0}
+             ]}
+        ]
+    }"#,
+    )
+    .unwrap();
+
+    let report = remap_coverage(&coverage, &generated, &sm);
+    assert_eq!(report.files().len(), 2);
+
+    let file = report.get_file("f.ts").unwrap();
+    let lines: Vec<_> = file.lines.iter().map(|(&l, &c)| (l, c)).collect();
+    assert_eq!(lines, vec![(1, 1), (2, 1), (3, 1), (5, 0), (7, 1)]);
+    assert_eq!(file.functions.len(), 1);
+    assert_eq!(file.functions[0].start, (0, 0));
+    assert_eq!(file.functions[0].end, (6, 0));
+    assert_eq!(file.branches.len(), 1);
+    assert_eq!(file.branches[0].start, (2, 4));
+    assert_eq!(file.branches[0].count, 0);
+
+    let main = report.get_file("main.ts").unwrap();
+    assert_eq!(main.lines.get(&1), Some(&1));
+    assert!(main.functions.is_empty());
+
+    let istanbul = report.to_istanbul();
+    let f = &istanbul["f.ts"];
+    assert_eq!(f["path"], "f.ts");
+    assert_eq!(f["fnMap"]["0"]["name"], "f");
+    assert_eq!(f["fnMap"]["0"]["loc"]["end"]["line"], 7);
+    assert_eq!(f["f"]["0"], 1);
+    assert_eq!(f["b"]["0"][0], 0);
+    assert_eq!(f["s"].as_object().unwrap().len(), 5);
+
+    let mut lcov = vec![];
+    report.to_lcov_writer(&mut lcov).unwrap();
+    let lcov = String::from_utf8(lcov).unwrap();
+    assert!(lcov.contains("SF:f.ts\nFN:1,f\nFNDA:1,f\nFNF:1\nFNH:1\nBRDA:3,0,0,0\nBRF:1\nBRH:0\n"));
+    assert!(lcov.contains("DA:5,0\nDA:7,1\nLF:5\nLH:4\nend_of_record\n"));
+}
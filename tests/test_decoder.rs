@@ -1,7 +1,10 @@
 use std::io;
 use std::io::BufRead;
 
-use sourcemap::{decode_data_url, DecodedMap, SourceMap, Token};
+use sourcemap::{
+    decode_data_url, decode_slice, decode_with_options, DecodeOptions, DecodedMap, Error,
+    SourceMap, Token,
+};
 
 #[test]
 fn test_no_header() {
@@ -182,3 +185,184 @@ fn test_sourcemap_range_mappings() {
     assert_eq!(iter.next().unwrap().to_tuple(), ("", 6, 0, Some("console")));
     assert!(iter.next().is_none());
 }
+
+#[test]
+fn test_lenient_decoding() {
+    // line 0: a segment with a bad size and one with an overflowing VLQ
+    // line 1: a reference to a non existing source
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "names": ["x"],
+        "mappings": "AAAA,CC,00000000000000,CAAC;AACA,CCAC,CDAAA"
+    }"#;
+    assert!(matches!(decode_slice(input), Err(Error::BadSegmentSize(2))));
+    assert!(decode_with_options(input, &DecodeOptions::default()).is_err());
+    let (map, warnings) = decode_with_options(
+        input,
+        &DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let positions: Vec<_> = warnings.iter().map(|w| (w.line, w.segment)).collect();
+    assert_eq!(positions, vec![(0, 1), (0, 2), (1, 1)]);
+    assert!(matches!(warnings[0].error, Error::BadSegmentSize(2)));
+    assert!(matches!(warnings[1].error, Error::VlqOverflow));
+    assert!(matches!(warnings[2].error, Error::BadSourceReference(1)));
+
+    let sm = match map {
+        DecodedMap::Regular(sm) => sm,
+        _ => panic!("unexpected sourcemap type"),
+    };
+    let tokens: Vec<_> = sm
+        .tokens()
+        .map(|t| (t.get_dst(), t.get_src(), t.get_name()))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            ((0, 0), (0, 0), None),
+            ((0, 2), (0, 1), None),
+            ((1, 0), (1, 1), None),
+            ((1, 2), (1, 2), Some("x")),
+        ]
+    );
+}
+
+#[test]
+fn test_lenient_decoding_keeps_columns() {
+    // the second segment only has two fields but still moves the column by 2
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "names": [],
+        "mappings": "AAAA,EC,GAAE"
+    }"#;
+    let (map, warnings) = decode_with_options(
+        input,
+        &DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(warnings[0].error, Error::BadSegmentSize(2)));
+
+    let sm = match map {
+        DecodedMap::Regular(sm) => sm,
+        _ => panic!("unexpected sourcemap type"),
+    };
+    assert_eq!(sm.get_token_count(), 2);
+    let token = sm.get_token(1).unwrap();
+    assert_eq!(token.get_dst_col(), 5);
+    assert_eq!(token.get_src(), (0, 2));
+}
+
+#[test]
+fn test_lenient_decoding_index() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sections": [
+            {
+                "offset": {"line": 0, "column": 0},
+                "map": {"version": 3, "sources": ["a.js"], "names": [], "mappings": "AAAA"}
+            },
+            {
+                "offset": {"line": 10, "column": 0},
+                "map": {"version": 3, "sources": ["b.js"], "names": [], "mappings": ";AAAA,CCAA"}
+            }
+        ]
+    }"#;
+    assert!(decode_slice(input).is_err());
+    let (map, warnings) = decode_with_options(
+        input,
+        &DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!((warnings[0].line, warnings[0].segment), (11, 1));
+    assert_eq!(map.lookup_token(11, 5).unwrap().get_source(), Some("b.js"));
+}
+
+#[test]
+fn test_decode_with_options_skips_values() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["coolstuff.js"],
+        "sourcesContent": ["var x = 1;\nalert(x);"],
+        "names": ["x", "alert"],
+        "rangeMappings": "B",
+        "mappings": "AAAA,GAAIA,GAAI,EACR,IAAIA,GAAK,EAAG,CACVC,MAAM"
+    }"#;
+
+    let (full, _) = decode_with_options(input, &DecodeOptions::default()).unwrap();
+    assert_eq!(full, decode_slice(input).unwrap());
+    let token = full.lookup_token(0, 4).unwrap();
+    assert_eq!(token.get_name(), Some("x"));
+    assert!(full.lookup_token(0, 0).unwrap().is_range());
+
+    let options = DecodeOptions {
+        with_source_contents: false,
+        with_names: false,
+        with_range_mappings: false,
+        ..Default::default()
+    };
+    let sm = match decode_with_options(input, &options).unwrap().0 {
+        DecodedMap::Regular(sm) => sm,
+        _ => panic!("unexpected sourcemap type"),
+    };
+    assert_eq!(sm.get_source_contents(0), None);
+    assert_eq!(sm.get_name_count(), 0);
+    let token = sm.lookup_token(0, 4).unwrap();
+    assert_eq!(token.get_name(), None);
+    assert_eq!(token.get_src(), (0, 4));
+    assert!(sm.tokens().all(|token| !token.is_range()));
+}
+
+#[test]
+fn test_decode_with_options_max_tokens() {
+    let input: &[_] =
+        br#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA,CAAC,CAAC;AACA"}"#;
+    let options = DecodeOptions {
+        max_tokens: Some(4),
+        ..Default::default()
+    };
+    assert!(decode_with_options(input, &options).is_ok());
+    let options = DecodeOptions {
+        max_tokens: Some(3),
+        ..Default::default()
+    };
+    assert!(matches!(
+        decode_with_options(input, &options),
+        Err(Error::TooManyTokens(3))
+    ));
+}
+
+#[test]
+fn test_decode_with_options_without_facebook_extensions() {
+    let input = std::fs::read("tests/fixtures/react-native-hermes/output.map").unwrap();
+    let (map, _) = decode_with_options(&input, &DecodeOptions::default()).unwrap();
+    assert!(matches!(map, DecodedMap::Hermes(_)));
+
+    let options = DecodeOptions {
+        with_facebook_extensions: false,
+        ..Default::default()
+    };
+    let (map, _) = decode_with_options(&input, &options).unwrap();
+    let sm = match map {
+        DecodedMap::Regular(sm) => sm,
+        _ => panic!("unexpected sourcemap type"),
+    };
+    let hermes = match decode_slice(&input).unwrap() {
+        DecodedMap::Hermes(smh) => smh,
+        _ => panic!("unexpected sourcemap type"),
+    };
+    assert_eq!(&sm, &*hermes);
+}
@@ -0,0 +1,67 @@
+use sourcemap::{diff, MappingChange, SourceMap};
+
+#[test]
+fn test_diff() {
+    let old = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sources": ["a.js", "b.js", "c.js"],
+            "sourcesContent": ["a", "b", null],
+            "names": ["x", "y"],
+            "mappings": "AAAAA,IAAIC,ICAA,IAAA,IAAA"
+        }"#,
+    )
+    .unwrap();
+
+    // the same mappings with reordered sources and names
+    let reordered = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sources": ["c.js", "b.js", "a.js"],
+            "sourcesContent": [null, "b", "a"],
+            "names": ["y", "x"],
+            "mappings": "AEAAC,IAAID,IDAA,IAAA,IAAA"
+        }"#,
+    )
+    .unwrap();
+    assert_ne!(old, reordered);
+    assert!(diff(&old, &reordered).is_empty());
+
+    let new = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sourceRoot": "",
+            "sources": ["a.js", "b.js", "d.js"],
+            "sourcesContent": ["a", "b2", "d"],
+            "names": ["x", "z"],
+            "mappings": "AAAAA,IAAIC,ICAA,IAAA;ACAJ"
+        }"#,
+    )
+    .unwrap();
+    let changes = diff(&old, &new);
+    assert_eq!(changes.added_sources, vec!["d.js"]);
+    assert_eq!(changes.removed_sources, vec!["c.js"]);
+    assert_eq!(changes.changed_contents, vec!["b.js"]);
+    let kinds: Vec<_> = changes
+        .mappings
+        .iter()
+        .map(|mapping| (mapping.dst_line, mapping.dst_col, mapping.change()))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (0, 4, MappingChange::Changed),
+            (0, 16, MappingChange::Removed),
+            (1, 0, MappingChange::Added),
+        ]
+    );
+    assert_eq!(
+        changes.to_string(),
+        "+ source d.js\n\
+         - source c.js\n\
+         ~ contents b.js\n\
+         0:4: a.js:0:4 name=y -> a.js:0:4 name=z\n\
+         0:16: - b.js:0:4\n\
+         1:0: + d.js:0:0\n"
+    );
+}
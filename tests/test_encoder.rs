@@ -1,4 +1,8 @@
-use sourcemap::SourceMap;
+use serde_json::{json, Value};
+use sourcemap::{
+    decode_with_options, BorrowedSourceMap, DecodeOptions, DecodedMap, EncodeOptions,
+    IgnoreListKey, RewriteOptions, SourceMap, SourceMapIndex,
+};
 
 #[test]
 fn test_basic_sourcemap() {
@@ -94,3 +98,149 @@ fn test_sourcemap_serializes_camel_case_debug_id() {
         serialized
     );
 }
+
+#[test]
+fn test_extensions_roundtrip() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["coolstuff.js"],
+        "names": ["x"],
+        "mappings": "AAAAA",
+        "x_sentry_meta": {"release": "1.0", "dist": null},
+        "x_metro_module_paths": ["a.js"],
+        "x_custom_tool": [1, 2, 3]
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert_eq!(
+        sm.extensions()
+            .iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>(),
+        vec!["x_custom_tool", "x_metro_module_paths", "x_sentry_meta"]
+    );
+    assert_eq!(
+        sm.extensions()
+            .get_as::<Vec<String>>("x_metro_module_paths")
+            .unwrap(),
+        Some(vec!["a.js".to_string()])
+    );
+    let (map, _) = decode_with_options(input, &DecodeOptions::default()).unwrap();
+    assert_eq!(map, DecodedMap::Regular(sm.clone()));
+    assert!(BorrowedSourceMap::from_slice(input).is_ok());
+
+    let rewritten = sm.rewrite(&RewriteOptions::default()).unwrap();
+    let mut out: Vec<u8> = vec![];
+    rewritten.to_writer(&mut out).unwrap();
+    let value: Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(
+        value["x_sentry_meta"],
+        json!({"release": "1.0", "dist": null})
+    );
+    assert_eq!(value["x_metro_module_paths"], json!(["a.js"]));
+    assert_eq!(value["x_custom_tool"], json!([1, 2, 3]));
+}
+
+#[test]
+fn test_set_extension() {
+    let mut sm =
+        SourceMap::from_slice(br#"{"version":3,"sources":[],"names":[],"mappings":""}"#).unwrap();
+    sm.extensions_mut()
+        .set("x_tool", &json!({"name": "bundler"}))
+        .unwrap();
+    sm.extensions_mut().set("x_count", &3u32).unwrap();
+    // keys of the format itself are never overwritten
+    sm.extensions_mut().set("mappings", "AAAA").unwrap();
+    assert_eq!(sm.extensions().get_as::<u32>("x_count").unwrap(), Some(3));
+    assert!(sm.extensions().get_as::<String>("x_count").is_err());
+    assert_eq!(sm.extensions_mut().remove("x_count"), Some(json!(3)));
+    assert_eq!(sm.extensions().get("x_count"), None);
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let value: Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(value["mappings"], "");
+    assert_eq!(value["x_tool"], json!({"name": "bundler"}));
+
+    let sm2 = SourceMap::from_slice(&out).unwrap();
+    assert_eq!(
+        sm2.extensions().get("x_tool"),
+        sm.extensions().get("x_tool")
+    );
+}
+
+#[test]
+fn test_index_extensions_roundtrip() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "x_sentry_meta": {"release": "1.0"},
+        "sections": [
+            {
+                "offset": {"line": 0, "column": 0},
+                "map": {
+                    "version": 3,
+                    "sources": ["a.js"],
+                    "names": [],
+                    "mappings": "AAAA",
+                    "x_section_meta": true
+                }
+            }
+        ]
+    }"#;
+    let smi = SourceMapIndex::from_slice(input).unwrap();
+    assert_eq!(
+        smi.extensions().get("x_sentry_meta"),
+        Some(&json!({"release": "1.0"}))
+    );
+
+    let mut out: Vec<u8> = vec![];
+    smi.to_writer(&mut out).unwrap();
+    let value: Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(value["x_sentry_meta"], json!({"release": "1.0"}));
+    assert_eq!(value["sections"][0]["map"]["x_section_meta"], json!(true));
+
+    let flat = smi.flatten().unwrap();
+    assert_eq!(
+        flat.extensions().get("x_sentry_meta"),
+        Some(&json!({"release": "1.0"}))
+    );
+
+    let (map, _) = decode_with_options(input, &DecodeOptions::default()).unwrap();
+    assert_eq!(map, DecodedMap::Index(smi));
+}
+
+#[test]
+fn test_legacy_ignore_list() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["app.js", "node_modules/lib.js"],
+        "names": [],
+        "mappings": "AAAA,ICAA",
+        "x_google_ignoreList": [1]
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert_eq!(sm.ignore_list().collect::<Vec<_>>(), vec![&1]);
+    assert_eq!(sm.extensions().get("x_google_ignoreList"), None);
+    let borrowed = BorrowedSourceMap::from_slice(input).unwrap();
+    assert_eq!(borrowed.ignore_list().collect::<Vec<_>>(), vec![&1]);
+
+    let encode = |key| {
+        let mut out: Vec<u8> = vec![];
+        let options = EncodeOptions {
+            ignore_list_key: key,
+        };
+        sm.to_writer_with_options(&mut out, &options).unwrap();
+        serde_json::from_slice::<Value>(&out).unwrap()
+    };
+
+    let standard = encode(IgnoreListKey::Standard);
+    assert_eq!(standard["ignoreList"], json!([1]));
+    assert_eq!(standard.get("x_google_ignoreList"), None);
+
+    let legacy = encode(IgnoreListKey::Legacy);
+    assert_eq!(legacy.get("ignoreList"), None);
+    assert_eq!(legacy["x_google_ignoreList"], json!([1]));
+
+    let both = encode(IgnoreListKey::Both);
+    assert_eq!(both["ignoreList"], json!([1]));
+    assert_eq!(both["x_google_ignoreList"], json!([1]));
+}
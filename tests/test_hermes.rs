@@ -1,4 +1,4 @@
-use sourcemap::SourceMapHermes;
+use sourcemap::{HermesFunctionMap, SourceMapHermes};
 
 #[test]
 fn test_react_native_hermes() {
@@ -25,6 +25,11 @@ fn test_react_native_hermes() {
         ("module.js", 1, 4, None)
     );
     assert_eq!(sm.get_original_function_name(11947), Some("foo"));
+
+    let frames = sm.lookup_frames(0, 11939);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].source, Some("module.js"));
+    assert_eq!(frames[0].name, Some("foo"));
 }
 
 #[test]
@@ -46,3 +51,104 @@ fn test_react_native_metro() {
     assert_eq!(token.to_tuple(), ("input.js", 2, 0, None));
     assert_eq!(sm.get_scope_for_token(token), Some("<global>"));
 }
+
+#[test]
+fn test_hermes_function_maps_roundtrip() {
+    let input: &[_] = include_bytes!("./fixtures/react-native-metro/output.js.map");
+    let sm = SourceMapHermes::from_reader(input).unwrap();
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+
+    let original: serde_json::Value = serde_json::from_slice(input).unwrap();
+    let written: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(
+        written["x_facebook_sources"],
+        original["x_facebook_sources"]
+    );
+}
+
+#[test]
+fn test_hermes_rewrite_roundtrip() {
+    let input: &[_] = include_bytes!("./fixtures/react-native-metro/output.js.map");
+    let sm = SourceMapHermes::from_reader(input).unwrap();
+    let sm = sm.rewrite(&Default::default()).unwrap();
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let sm = SourceMapHermes::from_slice(&out).unwrap();
+
+    let token = sm.lookup_token(1288, 10).unwrap();
+    assert_eq!(token.to_tuple(), ("module.js", 1, 10, None));
+    assert_eq!(sm.get_scope_for_token(token), Some("foo"));
+
+    let token = sm.lookup_token(1279, 18).unwrap();
+    assert_eq!(token.to_tuple(), ("input.js", 2, 0, None));
+    assert_eq!(sm.get_scope_for_token(token), Some("<global>"));
+}
+
+#[test]
+fn test_hermes_edit_function_maps() {
+    let input: &[_] = include_bytes!("./fixtures/react-native-hermes/output.map");
+    let mut sm = SourceMapHermes::from_reader(input).unwrap();
+
+    let token = sm.lookup_token(0, 11939).unwrap();
+    let src_id = token.get_src_id();
+    assert_eq!(token.to_tuple(), ("module.js", 1, 10, None));
+
+    let function_map = sm.get_function_map_mut(src_id).unwrap();
+    let idx = function_map.add_scope(2, 8, "bar");
+    assert_eq!(function_map.scopes()[idx].get_column(), 8);
+    assert_eq!(function_map.lookup_scope(2, 10), Some("bar"));
+    assert!(function_map.remove_scope(idx).is_some());
+    assert_eq!(function_map.lookup_scope(2, 10), Some("foo"));
+
+    let mut function_map = HermesFunctionMap::new();
+    function_map.add_scope(1, 0, "<global>");
+    function_map.add_scope(2, 0, "replaced");
+    sm.set_function_map(src_id, Some(function_map));
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let sm = SourceMapHermes::from_slice(&out).unwrap();
+    assert_eq!(sm.get_original_function_name(11939), Some("replaced"));
+
+    let function_map = sm.get_function_map(src_id).unwrap();
+    assert_eq!(function_map.names(), ["<global>", "replaced"]);
+}
+
+#[test]
+fn test_hermes_keeps_unparsed_metadata() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js", "b.js"],
+        "names": [],
+        "mappings": "AAAA,CCAA",
+        "x_facebook_sources": [
+            [{"names": ["<global>"], "mappings": "AAA"}, {"names": [], "mappings": "extra"}],
+            [{"names": ["broken"], "mappings": "!!"}]
+        ]
+    }"#;
+    let mut sm = SourceMapHermes::from_slice(input).unwrap();
+    assert!(sm.get_function_map(0).is_some());
+    assert!(sm.get_function_map(1).is_none());
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let original: serde_json::Value = serde_json::from_slice(input).unwrap();
+    let written: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(
+        written["x_facebook_sources"],
+        original["x_facebook_sources"]
+    );
+
+    sm.set_function_map(0, None);
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let written: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(written["x_facebook_sources"][0], serde_json::Value::Null);
+    assert_eq!(
+        written["x_facebook_sources"][1],
+        original["x_facebook_sources"][1]
+    );
+}
@@ -0,0 +1,100 @@
+use sourcemap::{IgnoreRule, IgnoreRules, RewriteOptions, SourceMap, SourceMapBuilder};
+
+#[test]
+fn test_glob_rules() {
+    let rule = |pattern: &str| IgnoreRule::Glob(pattern.into());
+
+    assert!(rule("node_modules/").is_match("webpack:///./node_modules/react/index.js"));
+    assert!(rule("node_modules/").is_match("node_modules/react/index.js"));
+    assert!(!rule("node_modules/").is_match("src/my_node_modules/index.js"));
+    assert!(!rule("node_modules/").is_match("src/node_modules"));
+
+    assert!(rule("webpack/bootstrap*").is_match("webpack:///webpack/bootstrap"));
+    assert!(rule("webpack/bootstrap*").is_match("webpack:///webpack/bootstrap 4ef8c2d"));
+    assert!(rule("(webpack)/buildin/").is_match("webpack:///(webpack)/buildin/global.js"));
+
+    assert!(rule("*.min.js").is_match("https://cdn.example.com/lib/jquery.min.js"));
+    assert!(!rule("*.min.js").is_match("src/app.js"));
+    assert!(rule("vendor/**/*.js").is_match("/app/vendor/a/b/c.js"));
+    assert!(!rule("vendor/*.js").is_match("/app/vendor/a/b/c.js"));
+    assert!(rule("src/?.js").is_match("src/a.js"));
+    assert!(!rule("src/?.js").is_match("src/ab.js"));
+}
+
+#[test]
+fn test_apply_rules() {
+    let mut builder = SourceMapBuilder::new(None);
+    let sources = [
+        "webpack:///webpack/bootstrap",
+        "webpack:///./src/app.js",
+        "webpack:///./vendor/lib.js",
+        "webpack:///./node_modules/a.js",
+    ];
+    for (idx, source) in sources.iter().enumerate() {
+        builder.add(0, idx as u32 * 10, 0, 0, Some(source), None, false);
+    }
+
+    let mut rules = IgnoreRules::default_rules();
+    rules.add_glob("vendor/");
+    assert!(rules.is_ignored("webpack:///./vendor/lib.js"));
+    assert!(!rules.is_ignored("webpack:///./src/app.js"));
+
+    rules.apply_to_builder(&mut builder);
+    let sm = builder.into_sourcemap();
+    assert_eq!(sm.ignore_list().collect::<Vec<_>>(), vec![&0, &2, &3]);
+
+    let mut sm = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sourceRoot": "webpack:///",
+            "sources": ["./node_modules/a.js", "./src/app.js"],
+            "names": [],
+            "mappings": "AAAA,CCAA"
+        }"#,
+    )
+    .unwrap();
+    IgnoreRules::default_rules().apply(&mut sm);
+    assert_eq!(sm.ignore_list().collect::<Vec<_>>(), vec![&0]);
+}
+
+#[test]
+fn test_rewrite_with_ignore_rules() {
+    let sm = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sources": ["/project/src/app.js", "/project/node_modules/a.js",
+                        "/project/src/generated.js"],
+            "names": [],
+            "mappings": "AAAA,CCAA,CCAA",
+            "ignoreList": [2]
+        }"#,
+    )
+    .unwrap();
+
+    let rules = IgnoreRules::default_rules();
+    let rewritten = sm
+        .clone()
+        .rewrite(&RewriteOptions {
+            ignore_rules: Some(&rules),
+            strip_prefixes: &["/project"],
+            ..Default::default()
+        })
+        .unwrap();
+    let ignored: Vec<_> = rewritten
+        .ignore_list()
+        .map(|&src_id| rewritten.get_source(src_id).unwrap())
+        .collect();
+    assert_eq!(ignored, vec!["node_modules/a.js", "src/generated.js"]);
+
+    let rewritten = sm.rewrite(&RewriteOptions::default()).unwrap();
+    assert_eq!(rewritten.ignore_list().collect::<Vec<_>>(), vec![&2]);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_rules() {
+    let mut rules = IgnoreRules::new();
+    rules.add_regex(regex::Regex::new(r"\.generated\.(js|ts)$").unwrap());
+    assert!(rules.is_ignored("src/api.generated.ts"));
+    assert!(!rules.is_ignored("src/api.ts"));
+}
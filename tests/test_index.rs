@@ -1,4 +1,6 @@
-use sourcemap::{DecodedMap, SourceMapIndex};
+use sourcemap::{
+    Bias, DecodedMap, Error, LookupOptions, Resolver, Result, SourceMap, SourceMapIndex,
+};
 use std::collections::HashMap;
 
 #[test]
@@ -206,6 +208,46 @@ fn test_flatten_indexed_sourcemap_with_ignore_list() {
     );
 }
 
+#[test]
+fn test_flatten_indexed_sourcemap_with_legacy_ignore_list() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sections": [
+            {
+                "offset": {"line": 0, "column": 0},
+                "map": {
+                    "version": 3,
+                    "sources": ["app.js", "vendor.js"],
+                    "names": [],
+                    "mappings": "AAAA,ICAA",
+                    "x_google_ignoreList": [1]
+                }
+            },
+            {
+                "offset": {"line": 1, "column": 0},
+                "map": {
+                    "version": 3,
+                    "sources": ["polyfill.js"],
+                    "names": [],
+                    "mappings": "AAAA",
+                    "ignoreList": [0],
+                    "x_google_ignoreList": []
+                }
+            }
+        ]
+    }"#;
+
+    let sm = SourceMapIndex::from_slice(input)
+        .unwrap()
+        .flatten()
+        .unwrap();
+    let ignored: Vec<_> = sm
+        .ignore_list()
+        .map(|&src_id| sm.get_source(src_id).unwrap())
+        .collect();
+    assert_eq!(ignored, vec!["vendor.js", "polyfill.js"]);
+}
+
 #[test]
 fn test_sourcemap_index_serializes_camel_case_debug_id() {
     const DEBUG_ID: &str = "fedcba9876543210fedcba9876543210";
@@ -235,3 +277,136 @@ fn test_sourcemap_index_serializes_camel_case_debug_id() {
         serialized
     );
 }
+
+const URL_SECTIONS: &[u8] = br#"{
+    "version": 3,
+    "file": "min.js",
+    "sections": [
+        {"offset": {"line": 0, "column": 0}, "url": "file1.js.map"},
+        {"offset": {"line": 1, "column": 0}, "url": "file2.js.map"}
+    ]
+}"#;
+
+fn load_section(url: &str) -> Option<SourceMap> {
+    let input: &[_] = match url {
+        "file1.js.map" => {
+            br#"{"version":3,"sources":["file1.js"],"names":["a"],"mappings":"AAAAA"}"#
+        }
+        "file2.js.map" => br#"{"version":3,"sources":["file2.js"],"names":[],"mappings":"AACA"}"#,
+        _ => return None,
+    };
+    Some(SourceMap::from_slice(input).unwrap())
+}
+
+#[test]
+fn test_resolve_sections() {
+    let mut smi = SourceMapIndex::from_slice(URL_SECTIONS).unwrap();
+    assert!(matches!(smi.flatten(), Err(Error::CannotFlatten(_))));
+
+    let mut requested = vec![];
+    smi.resolve_sections(|url| {
+        requested.push(url.to_string());
+        Ok(load_section(url)
+            .filter(|_| url == "file1.js.map")
+            .map(DecodedMap::Regular))
+    })
+    .unwrap();
+    assert_eq!(requested, vec!["file1.js.map", "file2.js.map"]);
+    assert!(smi.get_section(0).unwrap().get_sourcemap().is_some());
+    assert!(smi.get_section(1).unwrap().get_sourcemap().is_none());
+
+    // resolved sections are not requested again
+    requested.clear();
+    smi.resolve_sections(|url| {
+        requested.push(url.to_string());
+        Ok(load_section(url).map(DecodedMap::Regular))
+    })
+    .unwrap();
+    assert_eq!(requested, vec!["file2.js.map"]);
+
+    let sm = smi.flatten().unwrap();
+    let token = sm.lookup_token(1, 0).unwrap();
+    assert_eq!(token.get_source(), Some("file2.js"));
+    assert_eq!(token.get_src_line(), 1);
+    assert_eq!(sm.lookup_token(0, 0).unwrap().get_name(), Some("a"));
+
+    let err = SourceMapIndex::from_slice(URL_SECTIONS)
+        .unwrap()
+        .resolve_sections(|_| Err(Error::InvalidDataUrl));
+    assert!(matches!(err, Err(Error::InvalidDataUrl)));
+}
+
+struct SectionResolver;
+
+impl Resolver for SectionResolver {
+    fn fetch(&mut self, url: &str) -> Result<Option<Vec<u8>>> {
+        Ok(load_section(url).map(|sm| {
+            let mut out = vec![];
+            sm.to_writer(&mut out).unwrap();
+            out
+        }))
+    }
+}
+
+#[test]
+fn test_flatten_with_resolver() {
+    let smi = SourceMapIndex::from_slice(URL_SECTIONS).unwrap();
+    let sm = smi.flatten_with(&mut SectionResolver).unwrap();
+    assert_eq!(sm.get_source_count(), 2);
+    assert_eq!(
+        sm.lookup_token(1, 0).unwrap().get_source(),
+        Some("file2.js")
+    );
+    // the index itself is left untouched
+    assert!(smi.get_section(0).unwrap().get_sourcemap().is_none());
+
+    let missing: &[_] = br#"{
+        "version": 3,
+        "sections": [{"offset": {"line": 0, "column": 0}, "url": "missing.js.map"}]
+    }"#;
+    let smi = SourceMapIndex::from_slice(missing).unwrap();
+    assert!(matches!(
+        smi.flatten_with(&mut SectionResolver),
+        Err(Error::CannotFlatten(_))
+    ));
+}
+
+#[test]
+fn test_index_lookup_token_with_options() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sections": [
+            {
+                "offset": {"line": 0, "column": 0},
+                "map": {"version": 3, "sources": ["a.js"], "names": [], "mappings": "AAAA"}
+            },
+            {
+                "offset": {"line": 1, "column": 10},
+                "map": {"version": 3, "sources": ["b.js"], "names": [], "mappings": "EAAA"}
+            }
+        ]
+    }"#;
+    let map = sourcemap::decode_slice(input).unwrap();
+    let source = |line, col, options: &LookupOptions| {
+        map.lookup_token_with_options(line, col, options)
+            .and_then(|token| token.get_source())
+    };
+
+    assert_eq!(source(1, 5, &LookupOptions::default()), Some("a.js"));
+    let upper = LookupOptions {
+        bias: Bias::LeastUpperBound,
+        ..Default::default()
+    };
+    assert_eq!(source(0, 5, &upper), Some("b.js"));
+    assert_eq!(source(1, 10, &upper), Some("b.js"));
+    assert_eq!(source(1, 13, &upper), None);
+
+    let close_upper = LookupOptions {
+        bias: Bias::LeastUpperBound,
+        max_column_distance: Some(4),
+        ..Default::default()
+    };
+    assert_eq!(source(0, 5, &close_upper), None);
+    assert_eq!(source(1, 8, &close_upper), Some("b.js"));
+    assert_eq!(source(1, 7, &close_upper), None);
+}
@@ -1,4 +1,4 @@
-use sourcemap::{SourceMap, SourceMapBuilder};
+use sourcemap::{Bias, LookupOptions, SourceMap, SourceMapBuilder, SourceView, Token};
 
 #[test]
 fn test_basic_sourcemap() {
@@ -22,6 +22,9 @@ fn test_basic_sourcemap() {
         sm.lookup_token(0, 24).unwrap().to_tuple(),
         ("coolstuff.js", 2, 8, None)
     );
+    assert_eq!(sm.lookup_token(0, 3).unwrap().get_idx(), 1);
+    assert_eq!(sm.lookup_token(0, 10).unwrap().get_idx(), 3);
+    assert_eq!(sm.lookup_token(0, 1000).unwrap().get_idx(), 8);
 
     // Lines continue out to infinity
     assert_eq!(
@@ -56,3 +59,202 @@ fn test_basic_range() {
         ("input.js", 2, 14, None)
     );
 }
+
+#[test]
+fn test_lookup_generated() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 4, 2, Some("a.js"), None, false);
+    builder.add(0, 10, 5, 0, Some("a.js"), None, false);
+    builder.add(0, 20, 1, 0, Some("b.js"), None, false);
+    builder.add(1, 3, 4, 2, Some("a.js"), None, false);
+    builder.add(1, 8, 4, 9, Some("a.js"), None, false);
+    builder.add_raw(1, 12, 0, 0, None, None, false);
+    let sm = builder.into_sourcemap();
+
+    let positions = |tokens: Vec<Token<'_>>| tokens.iter().map(Token::get_dst).collect::<Vec<_>>();
+
+    assert_eq!(
+        positions(sm.lookup_generated("a.js", 4, 2)),
+        vec![(0, 0), (1, 3)]
+    );
+    assert_eq!(
+        positions(sm.lookup_generated("a.js", 4, 8)),
+        vec![(0, 0), (1, 3)]
+    );
+    assert_eq!(positions(sm.lookup_generated("a.js", 4, 20)), vec![(1, 8)]);
+    assert!(sm.lookup_generated("a.js", 4, 1).is_empty());
+    assert!(sm.lookup_generated("a.js", 3, 0).is_empty());
+    assert!(sm.lookup_generated("c.js", 4, 2).is_empty());
+
+    assert_eq!(
+        positions(sm.lookup_generated_range("a.js", (4, 0), (5, 0))),
+        vec![(0, 0), (1, 3), (1, 8)]
+    );
+    assert_eq!(
+        positions(sm.lookup_generated_range("a.js", (4, 5), (10, 0))),
+        vec![(1, 8), (0, 10)]
+    );
+    assert!(sm.lookup_generated_range("b.js", (2, 0), (1, 0)).is_empty());
+}
+
+#[test]
+fn test_lookup_range() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 3, 0, Some("a.js"), None, false);
+    builder.add(0, 5, 3, 8, Some("a.js"), None, false);
+    builder.add_raw(0, 8, 0, 0, None, None, false);
+    builder.add(0, 9, 10, 0, Some("b.js"), None, false);
+    builder.add(0, 12, 1, 4, Some("a.js"), None, false);
+    builder.add(1, 0, 3, 4, Some("a.js"), None, false);
+    builder.add(1, 4, 20, 2, Some("b.js"), None, true);
+    let sm = builder.into_sourcemap();
+
+    let spans = sm.lookup_range((0, 2), (1, 2));
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].source, "a.js");
+    // only the positions the tokens map to are covered
+    assert_eq!(
+        spans[0].spans,
+        vec![
+            ((1, 4), (1, 4)),
+            ((3, 0), (3, 0)),
+            ((3, 4), (3, 4)),
+            ((3, 8), (3, 8))
+        ]
+    );
+    assert_eq!(spans[1].source, "b.js");
+    assert_eq!(spans[1].spans, vec![((10, 0), (10, 0))]);
+
+    // range mappings cover every column up to the end of the range
+    let spans = sm.lookup_range((1, 6), (1, 10));
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].src_id, 1);
+    assert_eq!(spans[0].spans, vec![((20, 4), (20, 7))]);
+
+    assert!(sm.lookup_range((0, 8), (0, 9)).is_empty());
+    assert!(sm.lookup_range((5, 0), (6, 0)).is_empty());
+}
+
+#[test]
+fn test_lookup_range_zero_width_range_token() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 5, 2, 3, Some("a.js"), None, true);
+    builder.add(0, 5, 4, 0, Some("a.js"), None, false);
+    let sm = builder.into_sourcemap();
+
+    let spans = sm.lookup_range((0, 0), (0, 10));
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].spans, vec![((2, 3), (2, 3)), ((4, 0), (4, 0))]);
+}
+
+#[test]
+fn test_lookup_token_with_options() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+    builder.add(0, 10, 1, 0, Some("a.js"), None, false);
+    builder.add(2, 4, 2, 0, Some("a.js"), None, false);
+    builder.add(2, 30, 3, 0, Some("a.js"), None, true);
+    let sm = builder.into_sourcemap();
+
+    let lookup = |line, col, options: &LookupOptions| {
+        sm.lookup_token_with_options(line, col, options)
+            .map(|token| (token.get_src_line(), token.get_src_col()))
+    };
+
+    let lower = LookupOptions::default();
+    assert_eq!(lookup(0, 12, &lower), Some((1, 0)));
+    assert_eq!(lookup(1, 5, &lower), Some((1, 0)));
+    assert_eq!(lookup(2, 0, &lower), Some((1, 0)));
+
+    let upper = LookupOptions {
+        bias: Bias::LeastUpperBound,
+        ..Default::default()
+    };
+    assert_eq!(lookup(0, 10, &upper), Some((1, 0)));
+    assert_eq!(lookup(0, 12, &upper), Some((2, 0)));
+    assert_eq!(lookup(2, 31, &upper), None);
+
+    let same_line = LookupOptions {
+        same_line_only: true,
+        ..Default::default()
+    };
+    assert_eq!(lookup(1, 5, &same_line), None);
+    assert_eq!(lookup(2, 0, &same_line), None);
+    assert_eq!(lookup(2, 6, &same_line), Some((2, 0)));
+    let same_line_upper = LookupOptions {
+        bias: Bias::LeastUpperBound,
+        ..same_line
+    };
+    assert_eq!(lookup(0, 12, &same_line_upper), None);
+    assert_eq!(lookup(2, 0, &same_line_upper), Some((2, 0)));
+
+    let close = LookupOptions {
+        max_column_distance: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(lookup(0, 13, &close), Some((1, 0)));
+    assert_eq!(lookup(0, 14, &close), None);
+    assert_eq!(lookup(1, 0, &close), None);
+    // range tokens cover every column up to the next token
+    assert_eq!(lookup(2, 50, &close), Some((3, 20)));
+    let close_upper = LookupOptions {
+        bias: Bias::LeastUpperBound,
+        ..close
+    };
+    assert_eq!(lookup(2, 1, &close_upper), Some((2, 0)));
+    assert_eq!(lookup(2, 0, &close_upper), None);
+}
+
+#[test]
+fn test_tokens_seek() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+    builder.add(0, 10, 1, 0, Some("a.js"), None, false);
+    builder.add(0, 20, 2, 0, Some("a.js"), None, false);
+    let sm = builder.into_sourcemap();
+
+    let mut iter = sm.tokens();
+    assert!(iter.seek(0, 5));
+    assert_eq!(iter.next().map(|token| token.get_dst_col()), Some(10));
+}
+
+#[test]
+fn test_generated_spans() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 0, 0, Some("a.js"), None, false);
+    builder.add(0, 6, 1, 0, Some("a.js"), None, false);
+    builder.add(1, 2, 2, 0, Some("a.js"), None, false);
+    builder.add(1, 2, 3, 0, Some("a.js"), None, false);
+    builder.add(3, 0, 4, 0, Some("a.js"), None, false);
+    let sm = builder.into_sourcemap();
+    let generated = SourceView::from_string("let ä=1;ä++\n  f()\n\n".into());
+
+    let spans = |generated| {
+        sm.generated_spans(generated)
+            .map(|span| {
+                assert_eq!(span.token.get_dst_end(generated), span.end);
+                (span.start, span.end)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        spans(None),
+        vec![
+            ((0, 0), Some((0, 6))),
+            ((0, 6), None),
+            ((1, 2), Some((1, 2))),
+            ((1, 2), None),
+            ((3, 0), None),
+        ]
+    );
+    assert_eq!(
+        spans(Some(&generated)),
+        vec![
+            ((0, 0), Some((0, 6))),
+            ((0, 6), Some((0, 11))),
+            ((1, 2), Some((1, 2))),
+            ((1, 2), Some((1, 5))),
+            ((3, 0), Some((3, 0))),
+        ]
+    );
+}
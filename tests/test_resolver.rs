@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::fs;
+
+use sourcemap::stacktrace::{SourceMapLoader, Symbolicator};
+use sourcemap::{DecodedMap, Error, FsResolver, Resolver, Result, SourceMapBuilder};
+
+#[derive(Default)]
+struct MemoryResolver {
+    files: HashMap<String, Vec<u8>>,
+    fetched: Vec<String>,
+}
+
+impl MemoryResolver {
+    fn add(&mut self, url: &str, contents: &str) {
+        self.files.insert(url.into(), contents.as_bytes().to_vec());
+    }
+}
+
+impl Resolver for MemoryResolver {
+    fn fetch(&mut self, url: &str) -> Result<Option<Vec<u8>>> {
+        self.fetched.push(url.into());
+        Ok(self.files.get(url).cloned())
+    }
+}
+
+const SIMPLE_MAP: &str = r#"{
+    "version": 3,
+    "sourceRoot": "../src/",
+    "sources": ["foo.js"],
+    "names": [],
+    "mappings": "AAAA"
+}"#;
+
+#[test]
+fn test_resolve_relative_url() {
+    let mut resolver = MemoryResolver::default();
+    resolver.add(
+        "https://example.com/static/app.min.js",
+        "x()\n//# sourceMappingURL=maps/app.min.js.map",
+    );
+    resolver.add("https://example.com/static/maps/app.min.js.map", SIMPLE_MAP);
+    resolver.add("https://example.com/static/src/foo.js", "function x() {}");
+
+    let sm = resolver
+        .resolve_sourcemap("https://example.com/static/app.min.js")
+        .unwrap()
+        .unwrap();
+    let mut sm = match &*sm {
+        DecodedMap::Regular(sm) => sm.clone(),
+        _ => panic!("unexpected sourcemap type"),
+    };
+    assert_eq!(sm.get_source(0), Some("../src/foo.js"));
+
+    resolver
+        .load_source_contents("https://example.com/static/maps/app.min.js.map", &mut sm)
+        .unwrap();
+    assert_eq!(sm.get_source_contents(0), Some("function x() {}"));
+}
+
+#[test]
+fn test_resolve_data_url_and_missing() {
+    let mut builder = SourceMapBuilder::new(None);
+    builder.add(0, 0, 0, 0, Some("embedded.js"), None, false);
+    let data_url = builder.into_sourcemap().to_data_url().unwrap();
+
+    let mut resolver = MemoryResolver::default();
+    resolver.add(
+        "/dist/embedded.js",
+        &format!("x()\n//# sourceMappingURL={data_url}"),
+    );
+    resolver.add("/dist/nomap.js", "x()");
+
+    let sm = resolver
+        .resolve_sourcemap("/dist/embedded.js")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        sm.lookup_token(0, 0).unwrap().get_source(),
+        Some("embedded.js")
+    );
+    assert_eq!(resolver.fetched, vec!["/dist/embedded.js"]);
+
+    assert!(resolver
+        .resolve_sourcemap("/dist/nomap.js")
+        .unwrap()
+        .is_none());
+    assert!(resolver
+        .resolve_sourcemap("/dist/missing.js")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_resolve_index_sections() {
+    let mut resolver = MemoryResolver::default();
+    resolver.add(
+        "/dist/bundle.js.map",
+        r#"{
+            "version": 3,
+            "sections": [
+                {"offset": {"line": 0, "column": 0}, "url": "a.js.map"},
+                {"offset": {"line": 1, "column": 0}, "url": "/other/b.js.map"},
+                {"offset": {"line": 2, "column": 0}, "url": "missing.js.map"}
+            ]
+        }"#,
+    );
+    resolver.add(
+        "/dist/a.js.map",
+        r#"{"version":3,"sources":["a.js"],"names":[],"mappings":"AAAA"}"#,
+    );
+    resolver.add(
+        "/other/b.js.map",
+        r#"{"version":3,"sources":["b.js"],"names":[],"mappings":"AAAA"}"#,
+    );
+
+    let sm = resolver
+        .load_sourcemap("/dist/bundle.js.map")
+        .unwrap()
+        .unwrap();
+    let smi = match &*sm {
+        DecodedMap::Index(smi) => smi,
+        _ => panic!("unexpected sourcemap type"),
+    };
+    let sections: Vec<_> = smi
+        .sections()
+        .map(|section| section.get_sourcemap().is_some())
+        .collect();
+    assert_eq!(sections, vec![true, true, false]);
+    assert_eq!(sm.lookup_token(1, 0).unwrap().get_source(), Some("b.js"));
+}
+
+#[test]
+fn test_resolve_circular_sections() {
+    let mut resolver = MemoryResolver::default();
+    resolver.add(
+        "/dist/self.js.map",
+        r#"{
+            "version": 3,
+            "sections": [{"offset": {"line": 0, "column": 0}, "url": "self.js.map"}]
+        }"#,
+    );
+    resolver.add(
+        "/dist/a.js.map",
+        r#"{
+            "version": 3,
+            "sections": [{"offset": {"line": 0, "column": 0}, "url": "b.js.map"}]
+        }"#,
+    );
+    resolver.add(
+        "/dist/b.js.map",
+        r#"{
+            "version": 3,
+            "sections": [{"offset": {"line": 0, "column": 0}, "url": "a.js.map"}]
+        }"#,
+    );
+
+    assert!(matches!(
+        resolver.load_sourcemap("/dist/self.js.map"),
+        Err(Error::CircularReference(ref url)) if url == "/dist/self.js.map"
+    ));
+    assert!(matches!(
+        resolver.load_sourcemap("/dist/a.js.map"),
+        Err(Error::CircularReference(ref url)) if url == "/dist/a.js.map"
+    ));
+
+    // a failed load does not leave the URL marked as in progress
+    resolver.add(
+        "/dist/self.js.map",
+        r#"{"version":3,"sources":["self.js"],"names":[],"mappings":"AAAA"}"#,
+    );
+    assert!(resolver.load_sourcemap("/dist/self.js.map").is_ok());
+}
+
+#[test]
+fn test_symbolicate_with_resolver() {
+    let mut resolver = MemoryResolver::default();
+    resolver.add(
+        "https://example.com/app.min.js",
+        "x()\n//# sourceMappingURL=maps/app.min.js.map",
+    );
+    resolver.add("https://example.com/maps/app.min.js.map", SIMPLE_MAP);
+
+    // both traits can be used side by side
+    assert!(resolver
+        .load_sourcemap("https://example.com/maps/app.min.js.map")
+        .unwrap()
+        .is_some());
+    assert!(resolver
+        .load_for_url("https://example.com/app.min.js")
+        .is_some());
+
+    let mut symbolicator = Symbolicator::new(resolver);
+    let frames = symbolicator.symbolicate(
+        "Error: x\n    at x (https://example.com/app.min.js:1:1)\n    at https://example.com/missing.js:1:1",
+    );
+    assert_eq!(frames[0].source.as_deref(), Some("../src/foo.js"));
+    assert_eq!((frames[0].line, frames[0].column), (Some(1), Some(1)));
+    assert!(!frames[1].is_symbolicated());
+}
+
+#[test]
+fn test_fs_resolver_cache() {
+    let dir = std::env::temp_dir().join(format!("sourcemap-resolver-{}", std::process::id()));
+    fs::create_dir_all(dir.join("maps")).unwrap();
+    fs::write(
+        dir.join("app.min.js"),
+        "x()\n//# sourceMappingURL=maps/app.min.js.map\n",
+    )
+    .unwrap();
+    fs::write(dir.join("maps/app.min.js.map"), SIMPLE_MAP).unwrap();
+
+    let mut resolver = FsResolver::with_capacity(1);
+    let minified = dir.join("app.min.js");
+    let sm = resolver
+        .resolve_sourcemap(minified.to_str().unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        sm.lookup_token(0, 0).unwrap().get_source(),
+        Some("../src/foo.js")
+    );
+    assert_eq!(resolver.get_cached_count(), 1);
+
+    // served from the cache even after the file is gone
+    fs::remove_file(dir.join("maps/app.min.js.map")).unwrap();
+    let cached = resolver
+        .resolve_sourcemap(minified.to_str().unwrap())
+        .unwrap()
+        .unwrap();
+    assert!(std::sync::Arc::ptr_eq(&sm, &cached));
+
+    resolver.clear_cache();
+    assert!(resolver
+        .resolve_sourcemap(minified.to_str().unwrap())
+        .unwrap()
+        .is_none());
+    assert!(resolver
+        .fetch("https://example.com/app.min.js")
+        .unwrap()
+        .is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,264 @@
+use sourcemap::{
+    Callsite, DecodedMap, GeneratedRange, OriginalScope, OriginalScopeRef, RewriteOptions,
+    SourceMap, SourceMapBuilder, SourceView,
+};
+
+#[test]
+fn test_scopes_roundtrip() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["input.js"],
+        "names": ["module", "function", "foo", "x", "y", "a"],
+        "mappings": "AAAA",
+        "originalScopes": ["AAEA,CAOCCCC,EC,GA"],
+        "generatedRanges": "ASAA,EOACAEAK,C;E"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert!(sm.has_scopes());
+
+    let scope = sm.get_original_scope(0).unwrap();
+    assert_eq!(scope.get_kind(), Some("module"));
+    assert_eq!(scope.children[0].get_name(), Some("foo"));
+
+    let range = &sm.generated_ranges()[0];
+    let inlined = &range.children[0];
+    assert_eq!(
+        sm.get_scope_definition(inlined.definition.unwrap())
+            .and_then(OriginalScope::get_name),
+        Some("foo")
+    );
+    assert_eq!(
+        inlined.callsite,
+        Some(Callsite {
+            src_id: 0,
+            src_line: 2,
+            src_col: 0
+        })
+    );
+    assert_eq!(inlined.bindings, vec![Some("a".into())]);
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(r#""originalScopes":["AAEA,CAOCCCC,EC,GA"]"#));
+    assert!(out.contains(r#""generatedRanges":"ASAA,EOACAEAK,C;E""#));
+
+    let sm2 = SourceMap::from_slice(out.as_bytes()).unwrap();
+    assert_eq!(sm2.get_original_scope(0), sm.get_original_scope(0));
+    assert_eq!(sm2.generated_ranges(), sm.generated_ranges());
+}
+
+#[test]
+fn test_invalid_scopes_are_ignored() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["input.js"],
+        "names": [],
+        "mappings": "AAAA",
+        "originalScopes": ["AAEA"],
+        "generatedRanges": "ACCA,C"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert!(!sm.has_scopes());
+    assert_eq!(sm.get_token_count(), 1);
+}
+
+#[test]
+fn test_builder_scopes() {
+    let mut builder = SourceMapBuilder::new(None);
+    let src_id = builder.add_source("input.js");
+    builder.add_raw(0, 0, 0, 0, Some(src_id), None, false);
+
+    let mut scope = OriginalScope::new((0, 0), (10, 1));
+    scope.name = Some("outer".into());
+    scope.is_stack_frame = true;
+    builder.set_original_scope(src_id, Some(scope));
+
+    let mut range = GeneratedRange::new((0, 0), (0, 100));
+    range.definition = Some(OriginalScopeRef {
+        src_id,
+        scope_idx: 0,
+    });
+    range.is_stack_frame = true;
+    builder.add_generated_range(range);
+
+    let sm = builder.into_sourcemap();
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+
+    let sm = SourceMap::from_slice(&out).unwrap();
+    assert_eq!(sm.get_name(0), Some("outer"));
+    assert_eq!(
+        sm.get_original_scope(src_id)
+            .and_then(OriginalScope::get_name),
+        Some("outer")
+    );
+    assert_eq!(sm.generated_ranges()[0].end, (0, 100));
+}
+
+#[test]
+fn test_encode_unsorted_generated_ranges() {
+    let mut sm = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sources": ["input.js"],
+            "names": [],
+            "mappings": "AAAA"
+        }"#,
+    )
+    .unwrap();
+    let ranges = vec![
+        GeneratedRange::new((2, 0), (3, 0)),
+        GeneratedRange::new((0, 5), (1, 2)),
+    ];
+    sm.set_generated_ranges(ranges.clone());
+
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let decoded = SourceMap::from_slice(&out).unwrap();
+    assert_eq!(
+        decoded.generated_ranges(),
+        &[ranges[1].clone(), ranges[0].clone()]
+    );
+
+    // the lookup sees the sorted ranges as well
+    assert_eq!(sm.lookup_generated_ranges(0, 6), vec![&ranges[1]]);
+    assert_eq!(sm.lookup_generated_ranges(2, 1), vec![&ranges[0]]);
+
+    // only the ranges that overlap or do not nest in their parent cannot be
+    // encoded and are left out
+    let mut outer = GeneratedRange::new((4, 0), (6, 0));
+    outer.children = vec![
+        GeneratedRange::new((4, 2), (7, 0)),
+        GeneratedRange::new((5, 0), (5, 4)),
+    ];
+    sm.set_generated_ranges(vec![
+        GeneratedRange::new((0, 0), (2, 0)),
+        GeneratedRange::new((1, 0), (3, 0)),
+        outer,
+    ]);
+    let mut out: Vec<u8> = vec![];
+    sm.to_writer(&mut out).unwrap();
+    let decoded = SourceMap::from_slice(&out).unwrap();
+    let mut expected_outer = GeneratedRange::new((4, 0), (6, 0));
+    expected_outer.children = vec![GeneratedRange::new((5, 0), (5, 4))];
+    assert_eq!(
+        decoded.generated_ranges(),
+        &[GeneratedRange::new((0, 0), (2, 0)), expected_outer]
+    );
+    assert_eq!(decoded.get_token_count(), 1);
+}
+
+#[test]
+fn test_rewrite_remaps_scopes() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["unused.js", "input.js"],
+        "names": ["foo"],
+        "mappings": "ACAA",
+        "originalScopes": [null, "AAKA,KA"],
+        "generatedRanges": "ACCA,K"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert_eq!(
+        sm.generated_ranges()[0].definition,
+        Some(OriginalScopeRef {
+            src_id: 1,
+            scope_idx: 0
+        })
+    );
+
+    let sm = sm.rewrite(&RewriteOptions::default()).unwrap();
+    assert_eq!(sm.get_source(0), Some("input.js"));
+    assert_eq!(
+        sm.get_original_scope(0).and_then(OriginalScope::get_name),
+        Some("foo")
+    );
+    assert_eq!(
+        sm.generated_ranges()[0].definition,
+        Some(OriginalScopeRef {
+            src_id: 0,
+            scope_idx: 0
+        })
+    );
+}
+
+fn inlined_sourcemap() -> SourceMap {
+    let mut builder = SourceMapBuilder::new(None);
+    let src_id = builder.add_source("input.js");
+    builder.add_raw(0, 0, 1, 0, Some(src_id), None, false);
+    builder.add_raw(0, 12, 7, 2, Some(src_id), None, false);
+    builder.add_raw(0, 20, 4, 0, Some(src_id), None, false);
+
+    let mut root = OriginalScope::new((0, 0), (20, 0));
+    let mut outer = OriginalScope::new((1, 0), (5, 1));
+    outer.name = Some("outer".into());
+    outer.is_stack_frame = true;
+    let mut inner = OriginalScope::new((6, 0), (8, 1));
+    inner.name = Some("inner".into());
+    inner.is_stack_frame = true;
+    root.children = vec![outer, inner];
+    builder.set_original_scope(src_id, Some(root));
+
+    let mut range = GeneratedRange::new((0, 0), (0, 100));
+    range.definition = Some(OriginalScopeRef {
+        src_id,
+        scope_idx: 1,
+    });
+    range.is_stack_frame = true;
+    let mut inlined = GeneratedRange::new((0, 10), (0, 20));
+    inlined.definition = Some(OriginalScopeRef {
+        src_id,
+        scope_idx: 2,
+    });
+    inlined.callsite = Some(Callsite {
+        src_id,
+        src_line: 3,
+        src_col: 4,
+    });
+    range.children.push(inlined);
+    builder.add_generated_range(range);
+
+    builder.into_sourcemap()
+}
+
+#[test]
+fn test_lookup_frames_inlined() {
+    let sm = inlined_sourcemap();
+
+    assert_eq!(sm.lookup_generated_ranges(0, 12).len(), 2);
+    let frames = sm.lookup_frames(0, 12);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].name, Some("outer"));
+    assert_eq!(frames[0].source, Some("input.js"));
+    assert_eq!((frames[0].src_line, frames[0].src_col), (3, 4));
+    assert_eq!(frames[1].name, Some("inner"));
+    assert_eq!((frames[1].src_line, frames[1].src_col), (7, 2));
+
+    let frames = sm.lookup_frames(0, 50);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].name, Some("outer"));
+    assert_eq!((frames[0].src_line, frames[0].src_col), (4, 0));
+
+    let sv = SourceView::new("".into());
+    assert_eq!(
+        sm.get_original_function_name(0, 12, "x", &sv),
+        Some("inner")
+    );
+}
+
+#[test]
+fn test_lookup_frames_without_scopes() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["coolstuff.js"],
+        "names": ["x","alert"],
+        "mappings": "AAAA,GAAIA,GAAI,EACR,IAAIA,GAAK,EAAG,CACVC,MAAM"
+    }"#;
+    let sm = DecodedMap::from_reader(input).unwrap();
+    let frames = sm.lookup_frames(0, 3);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].source, Some("coolstuff.js"));
+    assert_eq!((frames[0].src_line, frames[0].src_col), (0, 4));
+    assert_eq!(frames[0].name, None);
+}
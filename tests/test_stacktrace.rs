@@ -0,0 +1,150 @@
+use sourcemap::stacktrace::{parse_stacktrace, SourceMapLoader, StackFrame, Symbolicator};
+use sourcemap::{DecodedMap, SourceMap, SourceView};
+
+fn frame(function: Option<&str>, url: &str, line: u32, column: Option<u32>) -> StackFrame {
+    StackFrame {
+        function: function.map(str::to_string),
+        url: url.to_string(),
+        line: Some(line),
+        column,
+    }
+}
+
+#[test]
+fn test_parse_v8_stacktrace() {
+    let stack = "TypeError: Cannot read properties of undefined (reading 'x')
+    at Object.foo (https://example.com/bundle.js:1:100)
+    at new Bar (https://example.com/bundle.js:2:5)
+    at async baz (file:///app/index.mjs:10:3)
+    at https://example.com/bundle.js:3:7
+    at eval (eval at run (https://example.com/bundle.js:4:9), <anonymous>:1:1)
+    at Array.map (<anonymous>)";
+    let frames = parse_stacktrace(stack);
+    assert_eq!(
+        frames,
+        vec![
+            frame(
+                Some("Object.foo"),
+                "https://example.com/bundle.js",
+                1,
+                Some(100)
+            ),
+            frame(Some("new Bar"), "https://example.com/bundle.js", 2, Some(5)),
+            frame(Some("baz"), "file:///app/index.mjs", 10, Some(3)),
+            frame(None, "https://example.com/bundle.js", 3, Some(7)),
+            frame(Some("eval"), "https://example.com/bundle.js", 4, Some(9)),
+            StackFrame {
+                function: Some("Array.map".into()),
+                url: "<anonymous>".into(),
+                line: None,
+                column: None,
+            },
+        ]
+    );
+    assert_eq!(
+        frames[0].to_string(),
+        "Object.foo (https://example.com/bundle.js:1:100)"
+    );
+}
+
+#[test]
+fn test_parse_gecko_and_jsc_stacktraces() {
+    let firefox = "foo@https://example.com/bundle.js:1:100
+bar/<@https://example.com/bundle.js:2:5
+@https://example.com/bundle.js:3:7
+run@https://example.com/bundle.js line 4 > eval:1:1";
+    assert_eq!(
+        parse_stacktrace(firefox),
+        vec![
+            frame(Some("foo"), "https://example.com/bundle.js", 1, Some(100)),
+            frame(Some("bar/<"), "https://example.com/bundle.js", 2, Some(5)),
+            frame(None, "https://example.com/bundle.js", 3, Some(7)),
+            frame(Some("run"), "https://example.com/bundle.js", 4, None),
+        ]
+    );
+
+    let safari = "foo@https://example.com/bundle.js:1:100
+global code@https://example.com/bundle.js:3:7
+https://example.com/bundle.js:5:1
+map@[native code]
+https://esm.sh/@babel/core.js:1:2
+https://unpkg.com/react@18.2.0/index.js:3:4
+/app/node_modules/@scope/pkg/index.js:5:6";
+    let frames = parse_stacktrace(safari);
+    assert_eq!(frames.len(), 7);
+    assert_eq!(frames[1].function.as_deref(), Some("global code"));
+    assert_eq!(
+        frames[2],
+        frame(None, "https://example.com/bundle.js", 5, Some(1))
+    );
+    assert_eq!(frames[3].url, "[native code]");
+    assert_eq!(frames[3].line, None);
+    assert_eq!(
+        frames[4],
+        frame(None, "https://esm.sh/@babel/core.js", 1, Some(2))
+    );
+    assert_eq!(
+        frames[5],
+        frame(None, "https://unpkg.com/react@18.2.0/index.js", 3, Some(4))
+    );
+    assert_eq!(
+        frames[6],
+        frame(None, "/app/node_modules/@scope/pkg/index.js", 5, Some(6))
+    );
+}
+
+struct TestLoader {
+    loads: usize,
+}
+
+impl SourceMapLoader for TestLoader {
+    fn load_for_url(&mut self, url: &str) -> Option<DecodedMap> {
+        if url != "https://example.com/bundle.js" {
+            return None;
+        }
+        self.loads += 1;
+        let input: &[_] = br#"{
+            "version": 3,
+            "sources": ["src/math.js"],
+            "sourcesContent": ["// math\nfunction divide(a, b) {\n  throw new Error('nope');\n}\n"],
+            "names": ["divide", "a", "b", "Error"],
+            "mappings": "CACAA,SAASA,EAACC,EAAEC,CAAC,CACX,MAAM,IAAIC"
+        }"#;
+        Some(DecodedMap::Regular(SourceMap::from_slice(input).unwrap()))
+    }
+
+    fn load_minified_source(&mut self, _url: &str) -> Option<SourceView> {
+        Some(SourceView::new(
+            " function d(a,b){throw new Error('nope')}".into(),
+        ))
+    }
+}
+
+#[test]
+fn test_symbolicate() {
+    let stack = "Error: nope
+    at d (https://example.com/bundle.js:1:18)
+    at d (https://example.com/bundle.js:1:25)
+    at https://example.com/vendor.js:1:1";
+
+    let mut symbolicator = Symbolicator::new(TestLoader { loads: 0 });
+    symbolicator.set_context_lines(1);
+    let frames = symbolicator.symbolicate(stack);
+    assert_eq!(frames.len(), 3);
+
+    let first = &frames[0];
+    assert!(first.is_symbolicated());
+    assert_eq!(first.source.as_deref(), Some("src/math.js"));
+    assert_eq!((first.line, first.column), (Some(3), Some(3)));
+    assert_eq!(first.function.as_deref(), Some("divide"));
+    assert_eq!(first.pre_context, vec!["function divide(a, b) {"]);
+    assert_eq!(
+        first.context_line.as_deref(),
+        Some("  throw new Error('nope');")
+    );
+    assert_eq!(first.post_context, vec!["}"]);
+
+    assert_eq!((frames[1].line, frames[1].column), (Some(3), Some(9)));
+    assert!(!frames[2].is_symbolicated());
+    assert_eq!(frames[2].raw.url, "https://example.com/vendor.js");
+}
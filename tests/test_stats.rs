@@ -0,0 +1,51 @@
+use sourcemap::{SourceMap, SourceView, UnmappedRun};
+
+#[test]
+fn test_sourcemap_stats() {
+    let generated = SourceView::new(
+        "function a(b){return b}\n\n    var c=a(1);\n//# sourceMappingURL=x.map".into(),
+    );
+    let sm = SourceMap::from_slice(
+        br#"{
+            "version": 3,
+            "sources": ["a.js", "b.js", "a.js"],
+            "sourcesContent": ["function a(b) { return b; }", null, null],
+            "names": ["a", "b", "unused"],
+            "mappings": "AAAA,SAASA,EAAEC,EAAG,CAAE,O;;IEAhB,IAAI;"
+        }"#,
+    )
+    .unwrap();
+
+    let stats = sm.stats(&generated);
+    assert_eq!(stats.token_count, 8);
+    assert_eq!(stats.tokens_per_line, vec![6, 0, 2, 0]);
+    assert_eq!(stats.tokens_without_source, 1);
+    assert_eq!(stats.tokens_with_name, 2);
+    assert_eq!(stats.name_count, 3);
+    assert_eq!(stats.used_name_count, 2);
+    assert_eq!(stats.sources_without_content, vec!["b.js", "a.js"]);
+    assert_eq!(stats.duplicate_sources, vec!["a.js"]);
+
+    // `b}` at the end of the first line and the indentation of the third
+    // line are unmapped
+    assert_eq!(stats.total_columns, 23 + 15);
+    assert_eq!(stats.mapped_columns, 21 + 11);
+    assert_eq!(stats.total_bytes, stats.total_columns);
+    assert_eq!(
+        stats.longest_unmapped_runs,
+        vec![
+            UnmappedRun {
+                line: 2,
+                start_col: 0,
+                end_col: 4,
+            },
+            UnmappedRun {
+                line: 0,
+                start_col: 21,
+                end_col: 23,
+            },
+        ]
+    );
+    assert_eq!(stats.names_coverage(), 2.0 * 100.0 / 7.0);
+    assert!(stats.mapped_columns_percentage() < 85.0);
+}
@@ -0,0 +1,133 @@
+use sourcemap::{MappingsIter, SourceMap, SourceMapHermes, StreamingSourceMap};
+
+#[test]
+fn test_streaming_matches_regular_decoder() {
+    let input: &[_] = include_bytes!("./fixtures/react-native-metro/output.js.map");
+    // the hermes function maps are skipped by the streaming decoder
+    let hermes = SourceMapHermes::from_slice(input).unwrap();
+    let sm: &SourceMap = &hermes;
+    let streaming = StreamingSourceMap::from_slice(input).unwrap();
+
+    assert_eq!(streaming.get_source_count(), sm.get_source_count());
+    assert_eq!(streaming.get_name_count(), sm.get_name_count());
+    let tokens = streaming.tokens().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(tokens.len(), sm.get_token_count() as usize);
+
+    for token in sm.tokens().step_by(97) {
+        let (line, col) = token.get_dst();
+        let raw = streaming.lookup_token(line, col).unwrap().unwrap();
+        assert_eq!(raw, token.get_raw_token());
+    }
+
+    for idx in 0..sm.get_source_count() {
+        assert_eq!(
+            streaming.load_source_contents(idx).unwrap().as_deref(),
+            sm.get_source_contents(idx)
+        );
+    }
+
+    assert_eq!(&streaming.to_sourcemap(true).unwrap(), sm);
+}
+
+#[test]
+fn test_streaming_lazy_source_contents() {
+    let input: &[_] = br#")]}'
+{
+    "version": 3,
+    "sourceRoot": "webpack:///",
+    "sources": ["a.js", "b.js"],
+    "sourcesContent": ["var a;", null],
+    "names": ["a", 42],
+    "rangeMappings": "B",
+    "mappings": "AAAA,IAAIA",
+    "ignoreList": [1]
+}"#;
+    let sm = StreamingSourceMap::from_slice(input).unwrap();
+    assert_eq!(sm.get_source(0), Some("webpack:///a.js"));
+    assert_eq!(sm.get_name(1), Some("42"));
+    assert_eq!(sm.ignore_list().collect::<Vec<_>>(), vec![&1]);
+
+    let range = sm.get_source_contents_range(0).unwrap();
+    assert_eq!(&input[range], b"\"var a;\"");
+    assert!(sm.get_source_contents_range(1).is_none());
+    assert!(sm.load_source_contents(1).unwrap().is_none());
+
+    let token = sm.lookup_token(0, 2).unwrap().unwrap();
+    assert!(token.is_range);
+    assert_eq!((token.dst_col, token.src_col), (0, 2));
+    assert_eq!(sm.lookup_token(0, 5).unwrap().unwrap().name_id, 0);
+
+    let regular = sm.to_sourcemap(false).unwrap();
+    assert_eq!(regular.get_source(1), Some("webpack:///b.js"));
+    assert_eq!(regular.get_source_contents(0), None);
+    assert_eq!(regular.get_token_count(), 2);
+}
+
+#[test]
+fn test_streaming_rejects_index() {
+    let input: &[_] = br#"{"version": 3, "sections": []}"#;
+    assert!(StreamingSourceMap::from_slice(input).is_err());
+}
+
+#[test]
+fn test_mappings_iter_errors() {
+    let mut iter = MappingsIter::new("AAAA,ACAA", None, 1, 0);
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+
+    let mut iter = MappingsIter::new(";;AAAAA", None, 1, 0);
+    assert!(iter.next().unwrap().is_err());
+}
+
+#[test]
+fn test_streaming_lookup_between_tokens() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js", "b.js"],
+        "names": ["x", "y"],
+        "mappings": "AAAA,IAAIA;;IACFC,GCAA;;;EAAE",
+        "rangeMappings": ";;B"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    let streaming = StreamingSourceMap::from_slice(input).unwrap();
+
+    // repeated lookups reuse the line starts of the first one
+    for _ in 0..2 {
+        for line in 0..8 {
+            for col in 0..8 {
+                let expected = sm.lookup_token(line, col).map(|token| {
+                    let mut raw = token.get_raw_token();
+                    raw.src_col = token.get_src_col();
+                    raw
+                });
+                assert_eq!(
+                    streaming.lookup_token(line, col).unwrap(),
+                    expected,
+                    "{}:{}",
+                    line,
+                    col
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_streaming_lookup_unsorted_columns() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "names": [],
+        "mappings": "UAAA,LAAC;AAAA"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    let streaming = StreamingSourceMap::from_slice(input).unwrap();
+
+    for col in 0..12 {
+        let expected = sm.lookup_token(0, col).map(|token| token.get_raw_token());
+        assert_eq!(streaming.lookup_token(0, col).unwrap(), expected, "{col}");
+    }
+    assert_eq!(streaming.lookup_token(0, 6).unwrap().unwrap().dst_col, 5);
+    assert_eq!(streaming.lookup_token(0, 11).unwrap().unwrap().dst_col, 10);
+}
@@ -0,0 +1,155 @@
+use sourcemap::{decode_slice, decode_strict, Diagnostic, DiagnosticKind, Error, SourceMap};
+
+fn kinds(diagnostics: &[Diagnostic]) -> Vec<&DiagnosticKind> {
+    diagnostics.iter().map(|d| &d.kind).collect()
+}
+
+#[test]
+fn test_validate_clean_sourcemap() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["coolstuff.js"],
+        "sourcesContent": ["var x = 1;\nalert(x);"],
+        "names": ["x", "alert"],
+        "mappings": "AAAA,IAAIA;AACJC,MAAMD"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert_eq!(sm.validate(), vec![]);
+    assert!(decode_strict(input).is_ok());
+}
+
+#[test]
+fn test_validate_source_positions() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js", "b.js"],
+        "sourcesContent": ["let foo;\nfoo();"],
+        "names": ["foo", "bar"],
+        "mappings": "AAAA,IAAIC,EAAQ,EAEZ"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    let diagnostics = sm.validate();
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![
+            &DiagnosticKind::SourcesContentLengthMismatch {
+                sources: 2,
+                sources_content: 1,
+            },
+            &DiagnosticKind::NameMismatch {
+                name: "bar".into(),
+                found: "foo".into(),
+            },
+            &DiagnosticKind::SourceColumnOutOfBounds {
+                src_id: 0,
+                src_line: 0,
+                src_col: 12,
+                line_length: 8,
+            },
+            &DiagnosticKind::SourceLineOutOfBounds {
+                src_id: 0,
+                src_line: 2,
+                line_count: 2,
+            },
+        ]
+    );
+    assert_eq!(diagnostics[1].position, Some((0, 4)));
+    assert_eq!(
+        diagnostics[1].to_string(),
+        "0:4: name \"bar\" does not match source text \"foo\""
+    );
+}
+
+#[test]
+fn test_validate_mappings_and_ignore_list() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sources": ["a.js"],
+        "names": [],
+        "ignoreList": [0, 3],
+        "mappings": "AAAA,EAAE,AAAA;EAAE,DAAE"
+    }"#;
+    let sm = SourceMap::from_slice(input).unwrap();
+    assert_eq!(
+        kinds(&sm.validate()),
+        vec![
+            &DiagnosticKind::IgnoreListOutOfBounds(3),
+            &DiagnosticKind::DuplicateMapping,
+        ]
+    );
+
+    let diagnostics = match decode_strict(input) {
+        Err(Error::ValidationFailed(diagnostics)) => diagnostics,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_eq!(
+        diagnostics[0],
+        Diagnostic {
+            position: Some((1, 1)),
+            kind: DiagnosticKind::UnsortedMapping,
+        }
+    );
+    assert_eq!(diagnostics.len(), 3);
+    assert!(Error::ValidationFailed(diagnostics)
+        .to_string()
+        .ends_with("(and 2 more)"));
+}
+
+#[test]
+fn test_validate_index() {
+    let input: &[_] = br#"{
+        "version": 3,
+        "sections": [
+            {
+                "offset": {"line": 0, "column": 0},
+                "map": {
+                    "version": 3,
+                    "sources": ["a.js"],
+                    "sourcesContent": ["a"],
+                    "names": [],
+                    "mappings": "AAAA;AACA"
+                }
+            },
+            {
+                "offset": {"line": 1, "column": 0},
+                "map": {
+                    "version": 3,
+                    "sources": ["b.js"],
+                    "names": [],
+                    "mappings": "EAAA,DAAA"
+                }
+            }
+        ]
+    }"#;
+    let map = decode_slice(input).unwrap();
+    let diagnostics = map.validate();
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                position: Some((1, 0)),
+                kind: DiagnosticKind::OverlappingSections { section: 0 },
+            },
+            Diagnostic {
+                position: Some((1, 0)),
+                kind: DiagnosticKind::SourceLineOutOfBounds {
+                    src_id: 0,
+                    src_line: 1,
+                    line_count: 1,
+                },
+            },
+        ]
+    );
+
+    let diagnostics = match decode_strict(input) {
+        Err(Error::ValidationFailed(diagnostics)) => diagnostics,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_eq!(
+        diagnostics[0],
+        Diagnostic {
+            position: Some((1, 1)),
+            kind: DiagnosticKind::UnsortedMapping,
+        }
+    );
+}